@@ -2,6 +2,9 @@ use clap::Parser;
 use durango::agent;
 use durango::game;
 use durango::game::ActionOutcome;
+use durango::record::{GameRecord, RecordedAction};
+use durango::rules::RuleSet;
+use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 
 #[derive(Parser)]
@@ -22,18 +25,47 @@ struct Args {
     ai_levels: Vec<usize>,
     #[clap(long)]
     seed: Option<u64>,
+    #[clap(long)]
+    max_rounds: Option<usize>,
+    /// Write a replayable record of each game to this file. With repeats,
+    /// the game number is added to the name.
+    #[clap(long)]
+    record: Option<String>,
 }
 
 fn interactive_action(g: &game::GameState) -> game::PlayerAction {
     use std::io::{self, Write};
     loop {
-        for (idx, card) in g.curr_player().hand.iter().enumerate() {
-            println!("  Card {}: {:?}", idx, card);
+        let me = g.curr_player();
+        for (idx, card) in me.hand.iter().enumerate() {
+            println!("  c{idx}: {card:?}");
+        }
+        for (idx, token) in me.tokens.iter().enumerate() {
+            println!("  t{idx}: {token:?}");
         }
-        for (dir, pos, node) in g.neighbors_of(g.curr_player().position) {
-            println!("  Move to {:?} at {:?} via {:?}", node.terrain, pos, dir);
+        for (dir, pos, node) in g.neighbors_of(me.position) {
+            println!(
+                "  {}: {:?} {} at {pos:?}",
+                dir.abbrev(),
+                node.terrain,
+                node.cost
+            );
         }
-        print!("Enter action: ");
+        for (idx, bcard) in g.shop.iter().enumerate() {
+            println!(
+                "  shop{idx}: {} for {} ({} left)",
+                bcard.card.name, bcard.cost, bcard.quantity
+            );
+        }
+        if g.has_open_shop() {
+            for (idx, bcard) in g.storage.iter().enumerate() {
+                println!(
+                    "  storage{idx}: {} for {} ({} left)",
+                    bcard.card.name, bcard.cost, bcard.quantity
+                );
+            }
+        }
+        print!("Enter action (e.g. move c0 E,NE / buy shop2 c0,c1 / end): ");
         io::stdout().flush().unwrap();
         let mut input = String::new();
         io::stdin().read_line(&mut input).unwrap();
@@ -42,7 +74,13 @@ fn interactive_action(g: &game::GameState) -> game::PlayerAction {
             println!("\nExiting.");
             std::process::exit(0);
         }
-        match serde_json::from_str(&input) {
+        // Raw JSON is still accepted.
+        let parsed = if input.trim_start().starts_with('{') {
+            serde_json::from_str(&input).map_err(|e| e.to_string())
+        } else {
+            input.parse()
+        };
+        match parsed {
             Ok(act) => return act,
             Err(e) => println!("Invalid action: {}", e),
         }
@@ -55,17 +93,53 @@ struct RunInfo {
     winner: usize,
 }
 
-fn run_game(args: &Args, rng: &mut impl Rng) -> Option<RunInfo> {
-    let mut g = match game::GameState::new(args.players, &args.preset, rng) {
-        Ok(game) => game,
+fn run_game(
+    args: &Args,
+    rng: &mut impl Rng,
+    record_path: Option<String>,
+) -> Option<RunInfo> {
+    let rules = RuleSet {
+        max_rounds: args.max_rounds,
+        ..Default::default()
+    };
+    let levels: Vec<usize> = (0..args.players)
+        .map(|i| args.ai_levels[i % args.ai_levels.len()])
+        .collect();
+    // Recorded games get their own seeded RNG for shuffles, so they can be
+    // replayed without the agents' choices.
+    let mut record = record_path.as_ref().map(|_| {
+        let players = (0..args.players).map(|i| format!("P{i}")).collect();
+        let mut record =
+            GameRecord::new(&args.preset, rng.random(), players, rules.clone());
+        record.agents = levels
+            .iter()
+            .enumerate()
+            .map(|(i, &level)| {
+                let is_user = args.interactive && i == 0;
+                (!is_user).then(|| agent::AgentSpec::from_difficulty(level))
+            })
+            .collect();
+        record
+    });
+    let setup = match &record {
+        Some(record) => record.start_game(),
+        None => game::GameState::new(args.players, &args.preset, &rules, rng)
+            .map(|g| (g, StdRng::from_rng(&mut *rng))),
+    };
+    let (mut g, mut game_rng) = match setup {
+        Ok(setup) => setup,
         Err(e) => {
             eprintln!("Error creating game state: {}", e);
             return None;
         }
     };
-    let ais = (0..args.players)
-        .map(|i| agent::create_agent(args.ai_levels[i % args.ai_levels.len()]))
+    let mut ais = levels
+        .iter()
+        .map(|&level| agent::create_agent(level))
         .collect::<Vec<_>>();
+    for (i, ai) in ais.iter_mut().enumerate() {
+        ai.on_game_start(&g, i);
+    }
     for a in 0..args.actions {
         if !args.quiet {
             println!("{}", g.curr_player().debug_str(g.curr_player_idx));
@@ -77,27 +151,49 @@ fn run_game(args: &Args, rng: &mut impl Rng) -> Option<RunInfo> {
             ais[g.curr_player_idx].choose_action(&g, rng)
         };
         if !args.quiet {
-            println!(" action: {:?}", &act);
+            println!(" action: {act}");
+        }
+        let (round_idx, player_idx) = (g.round_idx, g.curr_player_idx);
+        let result = g.process_action(&act, &mut game_rng);
+        if let Ok(outcome) = &result {
+            for ai in ais.iter_mut() {
+                ai.on_action(&g, player_idx, &act, outcome);
+            }
+            if let Some(record) = &mut record {
+                let action = RecordedAction::Action(act.clone());
+                record.add(round_idx, player_idx, action);
+            }
         }
-        match g.process_action(&act, rng) {
+        match result {
             Ok(ActionOutcome::GameOver) => {
+                for ai in ais.iter_mut() {
+                    ai.on_game_end(&g);
+                }
                 let finishers = g.players_at_finish();
                 let rounds = g.round_idx;
                 if !args.quiet {
                     println!(
-                        "Game over: {rounds} rounds, {a} actions, finished={finishers:?}",
+                        "Game over ({:?}): {rounds} rounds, {a} actions, finished={finishers:?}",
+                        g.end_reason.unwrap(),
                     );
                 }
+                if let (Some(record), Some(path)) = (&mut record, &record_path)
+                {
+                    record.set_result(&g);
+                    write_record(record, path);
+                }
+                let scores = g.player_scores();
+                let winner = (0..scores.len()).max_by_key(|&i| scores[i]);
                 return Some(RunInfo {
                     rounds,
                     actions: a,
-                    winner: finishers[0],
+                    winner: winner.unwrap(),
                 });
             }
             Ok(_) => {}
             Err(e) => {
                 println!(
-                    "Error processing {act:?} for player {}:\n{e}",
+                    "Error processing {act} for player {}:\n{e}",
                     g.curr_player_idx
                 );
                 if !is_user {
@@ -112,9 +208,18 @@ fn run_game(args: &Args, rng: &mut impl Rng) -> Option<RunInfo> {
         println!(" - {}", g.players[i].debug_str(i));
     }
     println!("Scores: {:?}\n", g.player_scores());
+    if let (Some(record), Some(path)) = (&record, &record_path) {
+        write_record(record, path);
+    }
     None
 }
 
+fn write_record(record: &GameRecord, path: &str) {
+    if let Err(e) = std::fs::write(path, record.to_string()) {
+        eprintln!("Error writing record to {path}: {e}");
+    }
+}
+
 const ALL_PRESETS: &[&str] = &[
     "first", "easy1", "easy2", "medium1", "medium2", "hard1", "hard2",
 ];
@@ -178,7 +283,11 @@ fn main() {
             args.preset = ALL_PRESETS[i % ALL_PRESETS.len()].to_string();
         }
         let start_time = std::time::Instant::now();
-        if let Some(info) = run_game(&args, &mut rng) {
+        let record_path = args.record.as_ref().map(|path| match args.repeats {
+            1 => path.clone(),
+            _ => format!("{path}.{i}"),
+        });
+        if let Some(info) = run_game(&args, &mut rng, record_path) {
             round_stats.add(info.rounds);
             action_stats.add(info.actions);
             win_counts[info.winner] += 1;
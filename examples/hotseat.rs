@@ -0,0 +1,484 @@
+use clap::Parser;
+use durango::agent::{Agent, AgentSpec, create_agent};
+use durango::data::{AxialCoord, Node, Terrain};
+use durango::game::{ActionOutcome, GameState, PlayerAction, Undo};
+use durango::record::{GameRecord, RecordedAction};
+use durango::rules::RuleSet;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::io::{self, Write};
+
+// A hot-seat terminal client: humans take turns at one keyboard, with bots
+// filling the other seats.
+//
+// cargo run --example hotseat -- --seats human,human,2 --preset easy2
+
+#[derive(Parser)]
+struct Args {
+    /// Who plays each seat: "human" (or "h"), or a bot difficulty level.
+    #[clap(long, value_delimiter = ',', default_value = "human,1")]
+    seats: Vec<String>,
+    #[clap(long, default_value = "easy1")]
+    preset: String,
+    #[clap(long)]
+    seed: Option<u64>,
+    /// Write a replayable record of the game to this file when it ends.
+    #[clap(long)]
+    record: Option<String>,
+    /// Color the map with ANSI escape codes.
+    #[clap(long)]
+    color: bool,
+}
+
+const HELP: &str = "\
+Commands:
+  move c0 E,NE         move along a path, using hand card 0
+  move c0,c1 t2 W      several cards and/or tokens at once
+  buy shop2 c0,c1      buy shop card 2 with hand cards 0 and 1
+  buy storage0 c1 t0   buy from storage, with a card and a token
+  draw c1 / draw t1    play a card's action, or a token to draw
+  trash c0,c2          trash hand cards
+  discard c2           discard hand cards
+  end                  finish the turn
+  undo                 take back the last action this turn
+  map                  show the map again
+  resign               leave the game
+  quit                 stop playing
+Map: J jungle, D desert, W water, V village, S swamp, C cave (with its
+token count), ^ mountain; the digit is the cost. @N is player N, * marks
+finish hexes and # hexes next to a barrier.";
+
+// An action this turn that can still be taken back. Agents only hear about
+// it once it's committed.
+struct Pending {
+    round_idx: usize,
+    player_idx: usize,
+    action: PlayerAction,
+    outcome: ActionOutcome,
+    undo: Undo,
+    // The game right after the action, to show the agents.
+    state: GameState,
+}
+
+struct Client {
+    game: GameState,
+    // Seeded by the record, for the game's own shuffles.
+    game_rng: StdRng,
+    // For the bots' choices.
+    agent_rng: StdRng,
+    // None for human seats.
+    agents: Vec<Option<Box<dyn Agent + Send>>>,
+    record: GameRecord,
+    // The current player's actions since their turn started, or since
+    // cards were drawn or a token was taken.
+    pending: Vec<Pending>,
+    color: bool,
+}
+
+impl Client {
+    fn new(args: &Args) -> Result<Self, Box<dyn std::error::Error>> {
+        if args.seats.len() > 4 {
+            return Err("At most 4 players can play".into());
+        }
+        let mut rng = match args.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        let mut agents = vec![];
+        let mut players = vec![];
+        let mut specs = vec![];
+        for (i, seat) in args.seats.iter().enumerate() {
+            if matches!(seat.as_str(), "human" | "h") {
+                agents.push(None);
+                players.push(format!("Human {i}"));
+                specs.push(None);
+            } else {
+                let level: usize = seat.parse().map_err(|_| {
+                    format!("Seat {seat:?} is not \"human\" or a bot level")
+                })?;
+                agents.push(Some(create_agent(level)));
+                players.push(format!("Bot {i}"));
+                specs.push(Some(AgentSpec::from_difficulty(level)));
+            }
+        }
+        let mut record = GameRecord::new(
+            &args.preset,
+            rng.random(),
+            players,
+            RuleSet::default(),
+        );
+        record.agents = specs;
+        let (game, game_rng) = record.start_game()?;
+        for (i, agent) in agents.iter_mut().enumerate() {
+            if let Some(agent) = agent {
+                agent.on_game_start(&game, i);
+            }
+        }
+        Ok(Self {
+            game,
+            game_rng,
+            agent_rng: StdRng::from_rng(&mut rng),
+            agents,
+            record,
+            pending: vec![],
+            color: args.color,
+        })
+    }
+
+    fn num_humans(&self) -> usize {
+        self.agents.iter().filter(|a| a.is_none()).count()
+    }
+
+    // Apply an action for the current player, returning false if the game
+    // is over.
+    fn take_action(&mut self, action: &PlayerAction) -> Result<bool, String> {
+        let player_idx = self.game.curr_player_idx;
+        let round_idx = self.game.round_idx;
+        let num_tokens = self.game.curr_player().tokens.len();
+        let (outcome, undo) = self.game.apply(action, &mut self.game_rng)?;
+        let game_over = matches!(outcome, ActionOutcome::GameOver);
+        self.pending.push(Pending {
+            round_idx,
+            player_idx,
+            action: action.clone(),
+            outcome,
+            undo,
+            state: self.game.clone(),
+        });
+        let turn_over = self.game.curr_player_idx != player_idx
+            || self.game.round_idx != round_idx;
+        // Drawn cards and cave tokens can't be unseen.
+        let revealed = matches!(action, PlayerAction::Draw(_))
+            || (!turn_over
+                && self.game.curr_player().tokens.len() > num_tokens);
+        // Bots can't take actions back.
+        let is_bot = self.agents[player_idx].is_some();
+        if turn_over || revealed || is_bot || game_over {
+            self.commit_pending();
+        }
+        if game_over {
+            for agent in self.agents.iter_mut().flatten() {
+                agent.on_game_end(&self.game);
+            }
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    // Move the pending actions into the record, so they can't be undone,
+    // and tell the agents about them.
+    fn commit_pending(&mut self) {
+        for p in self.pending.drain(..) {
+            for agent in self.agents.iter_mut().flatten() {
+                agent.on_action(&p.state, p.player_idx, &p.action, &p.outcome);
+            }
+            let action = RecordedAction::Action(p.action);
+            self.record.add(p.round_idx, p.player_idx, action);
+        }
+    }
+
+    fn undo(&mut self) -> Result<(), String> {
+        let p = self.pending.pop().ok_or(
+            "Nothing to undo: only actions this turn, since the last \
+                draw or cave, can be taken back",
+        )?;
+        println!("Undid: {}", p.action);
+        self.game.undo(p.undo);
+        Ok(())
+    }
+
+    // Returns false if the game is over.
+    fn resign(&mut self) -> Result<bool, String> {
+        self.commit_pending();
+        let player_idx = self.game.curr_player_idx;
+        let round_idx = self.game.round_idx;
+        let outcome = self.game.resign(player_idx, &mut self.game_rng)?;
+        self.record
+            .add(round_idx, player_idx, RecordedAction::Resign);
+        Ok(!matches!(outcome, ActionOutcome::GameOver))
+    }
+
+    // Play one action, by a bot or a human. Returns false once the game is
+    // over or the humans quit.
+    fn step(&mut self) -> bool {
+        let idx = self.game.curr_player_idx;
+        if let Some(agent) = &mut self.agents[idx] {
+            let action = agent.choose_action(&self.game, &mut self.agent_rng);
+            println!("P{idx}: {action}");
+            return match self.take_action(&action) {
+                Ok(running) => running,
+                Err(e) => {
+                    println!("Bot P{idx} made an illegal action: {e}");
+                    false
+                }
+            };
+        }
+        loop {
+            print!("P{idx}> ");
+            io::stdout().flush().unwrap();
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+                // EOF from Ctrl-D
+                println!();
+                return false;
+            }
+            let result = match input.trim() {
+                "" => continue,
+                "help" | "?" => {
+                    println!("{HELP}");
+                    continue;
+                }
+                "map" => {
+                    self.show_turn();
+                    continue;
+                }
+                "quit" | "exit" => return false,
+                "undo" => self.undo().map(|_| true),
+                "resign" => self.resign(),
+                text => text.parse().and_then(|act| self.take_action(&act)),
+            };
+            match result {
+                Ok(running) => return running,
+                Err(e) => println!("{e}"),
+            }
+        }
+    }
+
+    fn show_turn(&self) {
+        let g = &self.game;
+        println!("{}", draw_map(g, self.color));
+        self.show_barriers();
+        self.show_players();
+        self.show_market();
+        let me = g.curr_player();
+        println!("Round {}, P{}'s turn:", g.round_idx + 1, g.curr_player_idx);
+        for (idx, card) in me.hand.iter().enumerate() {
+            println!("  c{idx}: {card:?}");
+        }
+        for (idx, token) in me.tokens.iter().enumerate() {
+            println!("  t{idx}: {token:?}");
+        }
+        if me.trashes > 0 {
+            println!("  may trash {} card(s)", me.trashes);
+        }
+        for (dir, _, node) in g.neighbors_of(me.position) {
+            let name = match node.terrain {
+                Terrain::Invalid => "Mountain".to_string(),
+                terrain => format!("{terrain:?} {}", node.cost),
+            };
+            println!("  {}: {name}", dir.abbrev());
+        }
+    }
+
+    fn show_barriers(&self) {
+        for b in &self.game.barriers {
+            println!(
+                "# {:?} {} barrier between boards {} and {}",
+                b.terrain, b.cost, b.from_board, b.to_board
+            );
+        }
+    }
+
+    fn show_players(&self) {
+        let g = &self.game;
+        for (i, p) in g.players.iter().enumerate() {
+            let who = &self.record.players[i];
+            let status = if p.resigned {
+                "resigned".to_string()
+            } else {
+                let idx = g.map.node_idx(p.position).unwrap();
+                format!(
+                    "{} hexes to go, {} cards, {} tokens, {} barriers",
+                    g.graph.dists[idx],
+                    p.num_cards(),
+                    p.tokens.len(),
+                    p.broken_barriers.len()
+                )
+            };
+            let turn = if i == g.curr_player_idx { " <-" } else { "" };
+            println!("@{i} {who}: {status}{turn}");
+        }
+    }
+
+    fn show_market(&self) {
+        let g = &self.game;
+        for (idx, bcard) in g.shop.iter().enumerate() {
+            println!(
+                "  shop{idx}: {:?} for {} ({} left)",
+                bcard.card,
+                gold(bcard.cost),
+                bcard.quantity
+            );
+        }
+        if g.curses < g.rules.curses {
+            println!("  curses: {} left", g.curses);
+        }
+        let status = if g.has_open_shop() { "open" } else { "closed" };
+        println!("  storage ({status}):");
+        for (idx, bcard) in g.storage.iter().enumerate() {
+            println!(
+                "  storage{idx}: {:?} for {} ({} left)",
+                bcard.card,
+                gold(bcard.cost),
+                bcard.quantity
+            );
+        }
+    }
+}
+
+// Card costs are stored in half-gold units.
+fn gold(cost: u8) -> String {
+    match cost % 2 {
+        0 => format!("{}", cost / 2),
+        _ => format!("{}.5", cost / 2),
+    }
+}
+
+// Draw the map as rows of 3-character hexes, with each row offset by half a
+// hex: terrain glyph, cost, then a marker.
+fn draw_map(g: &GameState, color: bool) -> String {
+    let barrier_hexes: Vec<AxialCoord> = g
+        .barriers
+        .iter()
+        .flat_map(|b| b.edges.iter())
+        .flat_map(|&(pos, dir)| [pos, dir.neighbor_coord(pos)])
+        .collect();
+    let bonuses = g.bonus_counts();
+    // Doubled coordinates: x = 2q + r puts neighbors 2 apart in a row.
+    let cells: Vec<(i32, i32, String)> = g
+        .map
+        .all_nodes()
+        .map(|(pos, node)| {
+            let player = g
+                .players
+                .iter()
+                .position(|p| p.position == pos && !p.resigned);
+            let marker = if g.map.is_finish(pos) {
+                '*'
+            } else if barrier_hexes.contains(&pos) {
+                '#'
+            } else {
+                ' '
+            };
+            let text = match (player, node.terrain) {
+                (Some(i), _) => paint(&format!("@{i}{marker}"), "1;97", color),
+                (None, Terrain::Cave) => {
+                    let count = bonuses
+                        .iter()
+                        .find(|(p, _)| **p == pos)
+                        .map_or(0, |&(_, n)| n);
+                    paint(&format!("C{count}{marker}"), "36", color)
+                }
+                (None, Terrain::Invalid) => paint(" ^ ", "2", color),
+                (None, terrain) => {
+                    let text = format!("{}{}{marker}", glyph(node), node.cost);
+                    paint(&text, terrain_color(terrain), color)
+                }
+            };
+            (2 * pos.q + pos.r, pos.r, text)
+        })
+        .collect();
+    let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+    let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+    let max_y = cells.iter().map(|c| c.1).max().unwrap_or(0);
+    let mut rows = vec![vec![]; (max_y - min_y + 1) as usize];
+    for (x, y, text) in cells {
+        rows[(y - min_y) as usize].push(((x - min_x) as usize * 2, text));
+    }
+    let mut out = String::new();
+    for mut row in rows {
+        row.sort_by_key(|&(col, _)| col);
+        let mut width = 0;
+        for (col, text) in row {
+            out.push_str(&" ".repeat(col.saturating_sub(width)));
+            out.push_str(&text);
+            width = col + 3;
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn glyph(node: &Node) -> char {
+    match node.terrain {
+        Terrain::Jungle => 'J',
+        Terrain::Desert => 'D',
+        Terrain::Water => 'W',
+        Terrain::Village => 'V',
+        Terrain::Swamp => 'S',
+        Terrain::Cave => 'C',
+        Terrain::Invalid => '^',
+    }
+}
+
+fn terrain_color(terrain: Terrain) -> &'static str {
+    match terrain {
+        Terrain::Jungle => "32",
+        Terrain::Desert => "33",
+        Terrain::Water => "34",
+        Terrain::Village => "31",
+        Terrain::Swamp => "90",
+        Terrain::Cave => "36",
+        Terrain::Invalid => "2",
+    }
+}
+
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{code}m{text}\x1b[0m")
+    } else {
+        text.to_string()
+    }
+}
+
+fn wait_for_enter(prompt: &str) -> bool {
+    print!("{prompt}");
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap_or(0) > 0
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = Client::new(args)?;
+    println!("Type `help` for the list of commands.");
+    let mut shown = None;
+    loop {
+        let turn = (client.game.round_idx, client.game.curr_player_idx);
+        let is_human = client.agents[turn.1].is_none();
+        if is_human && shown != Some(turn) {
+            // Let humans swap seats before showing the next hand.
+            if client.num_humans() > 1
+                && !wait_for_enter(&format!(
+                    "Pass to P{} and press Enter...",
+                    turn.1
+                ))
+            {
+                break;
+            }
+            client.show_turn();
+            shown = Some(turn);
+        }
+        if !client.step() {
+            break;
+        }
+    }
+    client.commit_pending();
+    client.record.set_result(&client.game);
+    if let Some(reason) = &client.game.end_reason {
+        println!("{}", draw_map(&client.game, client.color));
+        println!("Game over ({reason:?}): {}", client.record.result);
+        client.show_players();
+    }
+    if let Some(path) = &args.record {
+        std::fs::write(path, client.record.to_string())?;
+        println!("Wrote the game record to {path}");
+    }
+    Ok(())
+}
+
+fn main() {
+    let args = Args::parse();
+    if let Err(e) = run(&args) {
+        eprintln!("{e}");
+    }
+}
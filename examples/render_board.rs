@@ -1,10 +1,14 @@
 use clap::Parser;
 use durango::data::{self, AxialCoord, HexMap, LayoutInfo, Node, Terrain};
+use durango::game::GameState;
 use durango::graph::HexGraph;
+use durango::render::{self, Scene, SvgOptions};
+use durango::rules::RuleSet;
 
 // Usage:
 // cargo run --example render_board -- -f dot | neato -Tsvg | display
 // cargo run --example render_board -- -f svg | display
+// cargo run --example render_board -- -f svg -p easy1 --players 3 | display
 
 #[derive(Parser)]
 struct Args {
@@ -19,6 +23,9 @@ struct Args {
     preset: Option<String>,
     #[clap(short, long, default_value = "dot")]
     format: String,
+    /// Render a new game with this many players on a preset (SVG only).
+    #[clap(long)]
+    players: Option<usize>,
 }
 
 fn coord_to_string(coord: &AxialCoord) -> String {
@@ -50,7 +57,7 @@ fn dump_dot(map: &HexMap, graph: &HexGraph) {
             coord_to_string(&coord),
             coord,
             node.cost,
-            node_color(&node)
+            node_color(node)
         );
         for (_, next_pos, neighbor) in graph.neighbors_of_idx(map, i) {
             if !matches!(neighbor.terrain, Terrain::Invalid) {
@@ -65,82 +72,37 @@ fn dump_dot(map: &HexMap, graph: &HexGraph) {
     println!("}}");
 }
 
-fn axial_to_center(pos: &AxialCoord, size: f32) -> (f32, f32) {
-    let x = size * (3.0_f32).sqrt() * (pos.q as f32 + pos.r as f32 / 2.0);
-    let y = size * 1.5 * pos.r as f32;
-    (x, y)
-}
-
-fn axial_to_polygon(pos: &AxialCoord, size: f32) -> String {
-    let (cx, cy) = axial_to_center(pos, size);
-    let mut points = Vec::new();
-    for i in 0..6 {
-        let angle =
-            std::f32::consts::PI / 3.0 * i as f32 + std::f32::consts::PI / 6.0;
-        let x = cx + size * angle.cos();
-        let y = cy + size * angle.sin();
-        points.push(format!("{},{}", x, y));
-    }
-    points.join(" ")
-}
-
 fn dump_svg(map: &HexMap, graph: &HexGraph, size: f32) {
     let dists = graph
         .distances_to_finish(map, 0, |node: &Node| (node.cost as f64).powi(2));
-    let mut min_center = (f32::INFINITY, f32::INFINITY);
-    let mut max_center = (f32::NEG_INFINITY, f32::NEG_INFINITY);
-    let mut elements = Vec::new();
-    for (i, (coord, node)) in map.all_nodes().enumerate() {
-        let (cx, cy) = axial_to_center(&coord, size);
-        if cx < min_center.0 {
-            min_center.0 = cx;
-        }
-        if cy < min_center.1 {
-            min_center.1 = cy;
-        }
-        if cx > max_center.0 {
-            max_center.0 = cx;
-        }
-        if cy > max_center.1 {
-            max_center.1 = cy;
-        }
-        // or use node.cost here
-        let label = if node.cost > 10 {
-            "X".into()
-        } else {
-            format!("{} ({})", node.cost, dists[i])
-        };
-
-        elements.push(format!(
-            "<g id=\"node{i}\" class=\"hex\">
-<polygon points=\"{}\" fill=\"{}\" stroke=\"black\" stroke-width=\"2\" />
-<text x=\"{cx}\" y=\"{cy}\" font-size=\"{}\" dominant-baseline=\"middle\" text-anchor=\"middle\">{label}</text>
-</g>",
-            axial_to_polygon(&coord, size),
-            node_color(&node),
-            size / 2.0,
-        ));
-    }
-
-    let margin = size * 1.1;
-    let width = (max_center.0 - min_center.0) + 2.0 * margin;
-    let height = (max_center.1 - min_center.1) + 2.0 * margin;
-    println!(
-        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
-        min_center.0 - margin,
-        min_center.1 - margin,
-        width,
-        height
-    );
-    for element in elements.into_iter() {
-        println!("{}", element);
-    }
-    println!("</svg>");
+    let labels = map
+        .all_nodes()
+        .enumerate()
+        .map(|(i, (_, node))| match node.cost {
+            cost if cost > 10 => "X".into(),
+            cost => format!("{cost} ({})", dists[i]),
+        })
+        .collect();
+    let options = SvgOptions {
+        hex_size: size,
+        labels: Some(labels),
+        ..Default::default()
+    };
+    print!("{}", render::render_svg(&Scene::from_map(map), &options));
 }
 
 fn render(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(num_players) = args.players {
+        let preset =
+            args.preset.as_deref().ok_or("--players needs a preset")?;
+        let rules = RuleSet::default();
+        let game =
+            GameState::new(num_players, preset, &rules, &mut rand::rng())?;
+        print!("{}", render::game_svg(&game, &SvgOptions::default()));
+        return Ok(());
+    }
     let map = match &args.preset {
-        Some(name) => HexMap::create_named(&name),
+        Some(name) => HexMap::create_named(name),
         None => {
             let layout_csv =
                 format!("board,rotation,q,r\n{}", args.layout.join("\n"));
@@ -0,0 +1,58 @@
+use clap::Parser;
+use durango::record::GameRecord;
+use durango::render::{self, SvgOptions};
+
+// Turn a game record (see autoplay --record) into an animated SVG, or a
+// sequence of SVG frames.
+//
+// cargo run --example replay_svg -- game.txt -o game.svg
+// cargo run --example replay_svg -- game.txt --frames frames/
+
+#[derive(Parser)]
+struct Args {
+    /// Game record to replay.
+    record: String,
+    /// Where to write the animated SVG, or stdout if not given.
+    #[clap(short, long)]
+    output: Option<String>,
+    /// Write one SVG per step into this directory instead.
+    #[clap(long)]
+    frames: Option<String>,
+    /// Seconds to show each step of the animation.
+    #[clap(long, default_value_t = 0.3)]
+    step_secs: f32,
+    /// Image width in pixels.
+    #[clap(long)]
+    width: Option<u32>,
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(&args.record)?;
+    let record = GameRecord::parse(&text)?;
+    let options = SvgOptions {
+        width: args.width,
+        ..Default::default()
+    };
+    if let Some(dir) = &args.frames {
+        std::fs::create_dir_all(dir)?;
+        let frames = render::replay_frames(&record, &options)?;
+        for (i, frame) in frames.iter().enumerate() {
+            std::fs::write(format!("{dir}/frame{i:04}.svg"), frame)?;
+        }
+        eprintln!("Wrote {} frames to {dir}", frames.len());
+        return Ok(());
+    }
+    let svg = render::replay_svg(&record, &options, args.step_secs)?;
+    match &args.output {
+        Some(path) => std::fs::write(path, svg)?,
+        None => print!("{svg}"),
+    }
+    Ok(())
+}
+
+fn main() {
+    let args = Args::parse();
+    if let Err(e) = run(&args) {
+        eprintln!("{e}");
+    }
+}
@@ -0,0 +1,52 @@
+use clap::Parser;
+use durango::agent::AgentSpec;
+use durango::dataset::{SelfPlayConfig, generate};
+use std::fs::File;
+use std::io::BufWriter;
+
+#[derive(Parser)]
+struct Args {
+    // Agent per seat: a difficulty level (as used by autoplay) or an
+    // AgentSpec JSON object.
+    #[clap(default_values = ["5", "5"])]
+    agents: Vec<String>,
+    // Output JSONL file.
+    #[clap(short, long, default_value = "selfplay.jsonl")]
+    out: String,
+    #[clap(short, long, default_value_t = 10)]
+    games: usize,
+    #[clap(long, value_parser, value_delimiter = ',', default_value = "easy1")]
+    layouts: Vec<String>,
+    #[clap(long)]
+    seed: Option<u64>,
+    #[clap(long)]
+    max_rounds: Option<usize>,
+}
+
+fn parse_agent(spec: &str) -> Result<AgentSpec, String> {
+    match spec.parse::<usize>() {
+        Ok(level) => Ok(AgentSpec::from_difficulty(level)),
+        Err(_) => serde_json::from_str(spec).map_err(|e| e.to_string()),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let agents = args
+        .agents
+        .iter()
+        .map(|a| parse_agent(a))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut config = SelfPlayConfig {
+        agents,
+        layouts: args.layouts,
+        games: args.games,
+        seed: args.seed,
+        ..Default::default()
+    };
+    config.rules.max_rounds = args.max_rounds;
+    let mut out = BufWriter::new(File::create(&args.out)?);
+    let num_records = generate(&config, &mut out)?;
+    println!("Wrote {num_records} records to {}", args.out);
+    Ok(())
+}
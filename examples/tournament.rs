@@ -0,0 +1,136 @@
+use clap::Parser;
+use durango::agent::AgentSpec;
+use durango::tournament::{
+    Entrant, Format, SprtConfig, TournamentConfig, run_sprt, run_tournament,
+};
+
+#[derive(Parser)]
+struct Args {
+    // Entrants as name=spec, where spec is a difficulty level (as used by
+    // autoplay), an AgentSpec JSON object, or @file with one (e.g. as written
+    // by the tune_weights or train_td examples).
+    #[clap(required = true)]
+    entrants: Vec<String>,
+    #[clap(long, value_parser, value_delimiter = ',', default_value = "easy1")]
+    layouts: Vec<String>,
+    #[clap(short, long, default_value_t = 10)]
+    games: usize,
+    #[clap(long)]
+    gauntlet: bool,
+    #[clap(long)]
+    threads: Option<usize>,
+    #[clap(long)]
+    seed: Option<u64>,
+    #[clap(long)]
+    max_rounds: Option<usize>,
+    // Run an SPRT of the first entrant against the second instead.
+    #[clap(long)]
+    sprt: bool,
+    #[clap(long, default_value_t = 20.0)]
+    elo1: f64,
+}
+
+fn parse_entrant(arg: &str) -> Result<Entrant, String> {
+    let (name, spec) = arg
+        .split_once('=')
+        .ok_or(format!("Expected name=spec, got {arg:?}"))?;
+    let agent = if let Some(path) = spec.strip_prefix('@') {
+        AgentSpec::load(path).map_err(|e| format!("{path}: {e}"))?
+    } else if let Ok(level) = spec.parse::<usize>() {
+        AgentSpec::from_difficulty(level)
+    } else {
+        serde_json::from_str(spec).map_err(|e| e.to_string())?
+    };
+    Ok(Entrant {
+        name: name.to_string(),
+        agent,
+    })
+}
+
+fn main() {
+    let args = Args::parse();
+    let entrants = match args
+        .entrants
+        .iter()
+        .map(|e| parse_entrant(e))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(entrants) => entrants,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let mut config = TournamentConfig {
+        format: if args.gauntlet {
+            Format::Gauntlet
+        } else {
+            Format::RoundRobin
+        },
+        layouts: args.layouts,
+        games_per_pairing: args.games,
+        seed: args.seed,
+        ..Default::default()
+    };
+    config.rules.max_rounds = args.max_rounds;
+    if let Some(threads) = args.threads {
+        config.threads = threads;
+    }
+
+    if args.sprt {
+        if entrants.len() != 2 {
+            eprintln!("SPRT needs exactly two entrants");
+            std::process::exit(1);
+        }
+        let sprt = SprtConfig {
+            elo1: args.elo1,
+            ..Default::default()
+        };
+        match run_sprt(&entrants[0], &entrants[1], &config, &sprt) {
+            Ok(r) => {
+                println!(
+                    "W/L/D: {}/{}/{}  LLR: {:.2} [{:.2}, {:.2}]",
+                    r.wins,
+                    r.losses,
+                    r.draws,
+                    r.llr,
+                    r.lower_bound,
+                    r.upper_bound
+                );
+                match r.accepted_h1 {
+                    Some(true) => println!(
+                        "H1 accepted: {} is stronger",
+                        entrants[0].name
+                    ),
+                    Some(false) => println!(
+                        "H0 accepted: {} is not stronger",
+                        entrants[0].name
+                    ),
+                    None => println!("No decision"),
+                }
+            }
+            Err(e) => eprintln!("Error: {e}"),
+        }
+        return;
+    }
+
+    match run_tournament(&entrants, &config) {
+        Ok(report) => {
+            for e in report.results.iter().flat_map(|r| &r.agent_errors) {
+                eprintln!("{e}");
+            }
+            println!(
+                "{:<16} {:>5} {:>5} {:>5} {:>12}",
+                "Name", "W", "L", "D", "Elo"
+            );
+            for r in &report.ratings {
+                let ci = r.elo_ci95.map_or("-".into(), |ci| format!("{ci:.0}"));
+                println!(
+                    "{:<16} {:>5} {:>5} {:>5} {:>6.0} ± {:<4}",
+                    r.name, r.wins, r.losses, r.draws, r.elo, ci
+                );
+            }
+        }
+        Err(e) => eprintln!("Error: {e}"),
+    }
+}
@@ -0,0 +1,76 @@
+use clap::Parser;
+use durango::agent::{
+    AgentSpec, DEFAULT_VALUE_WEIGHTS, LEARNED_WEIGHTS_FILE, SearchBudget,
+    TdConfig, load_value_weights, train_td,
+};
+
+#[derive(Parser)]
+struct Args {
+    // Where to save the learned agent, e.g. for the tournament example's
+    // name=@linear.json entrants. Difficulty level 8 loads it from here.
+    #[clap(short, long, default_value = LEARNED_WEIGHTS_FILE)]
+    out: String,
+    // Continue training from a previously saved linear agent.
+    #[clap(long)]
+    init: Option<String>,
+    #[clap(short, long, default_value_t = 100)]
+    games: usize,
+    #[clap(long, default_value_t = 0.01)]
+    alpha: f64,
+    #[clap(long, default_value_t = 0.7)]
+    lambda: f64,
+    #[clap(long, default_value_t = 0.05)]
+    epsilon: f64,
+    // Search iterations per action during self-play.
+    #[clap(long, default_value_t = 500)]
+    iterations: usize,
+    #[clap(long, value_parser, value_delimiter = ',', default_value = "easy1")]
+    layouts: Vec<String>,
+    #[clap(long)]
+    seed: Option<u64>,
+    #[clap(long)]
+    max_rounds: Option<usize>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let mut weights = match &args.init {
+        Some(path) => load_value_weights(path)?,
+        None => DEFAULT_VALUE_WEIGHTS.to_vec(),
+    };
+    let mut config = TdConfig {
+        games: args.games,
+        alpha: args.alpha,
+        lambda: args.lambda,
+        epsilon: args.epsilon,
+        budget: SearchBudget::iterations(args.iterations),
+        layouts: args.layouts,
+        seed: args.seed,
+        ..Default::default()
+    };
+    config.rules.max_rounds = args.max_rounds;
+
+    let save = |weights: &[f64]| {
+        let spec = AgentSpec::Linear {
+            weights: weights.to_vec(),
+            weights_file: None,
+            budget: SearchBudget {
+                max_iterations: Some(20_000),
+                max_time_ms: Some(1_000),
+            },
+        };
+        let json = serde_json::to_string_pretty(&spec).unwrap();
+        if let Err(e) = std::fs::write(&args.out, json) {
+            eprintln!("Failed to write {}: {e}", args.out);
+        }
+    };
+    train_td(&config, &mut weights, |game_idx, weights| {
+        if (game_idx + 1) % 10 == 0 {
+            println!("Game {}: {weights:.3?}", game_idx + 1);
+            save(weights);
+        }
+    })?;
+    save(&weights);
+    println!("Wrote {}", args.out);
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use clap::Parser;
+use durango::agent::AgentSpec;
+use durango::tuning::{TuningConfig, tune_weights};
+
+#[derive(Parser)]
+struct Args {
+    // Difficulty level to start from: 2..=4 (static), 5 (dynamic) or
+    // 7 (rival), or an AgentSpec JSON object.
+    #[clap(long, default_value = "5")]
+    base: String,
+    // Where to write the best spec found, e.g. for the tournament example's
+    // name=@weights.json entrants.
+    #[clap(short, long, default_value = "weights.json")]
+    out: String,
+    #[clap(long, default_value_t = 3)]
+    passes: usize,
+    #[clap(long, default_value_t = 0.25)]
+    step: f64,
+    #[clap(short, long, default_value_t = 20)]
+    games: usize,
+    #[clap(long, value_parser, value_delimiter = ',', default_value = "easy1")]
+    layouts: Vec<String>,
+    #[clap(long)]
+    threads: Option<usize>,
+    #[clap(long, default_value_t = 1)]
+    seed: u64,
+    #[clap(long)]
+    max_rounds: Option<usize>,
+}
+
+fn main() {
+    let args = Args::parse();
+    let base = match args.base.parse::<usize>() {
+        Ok(level) => AgentSpec::from_difficulty(level),
+        Err(_) => match serde_json::from_str(&args.base) {
+            Ok(spec) => spec,
+            Err(e) => {
+                eprintln!("Invalid base agent: {e}");
+                std::process::exit(1);
+            }
+        },
+    };
+    let mut config = TuningConfig {
+        passes: args.passes,
+        step: args.step,
+        ..Default::default()
+    };
+    config.matches.layouts = args.layouts;
+    config.matches.games_per_pairing = args.games;
+    config.matches.seed = Some(args.seed);
+    config.matches.rules.max_rounds = args.max_rounds;
+    if let Some(threads) = args.threads {
+        config.matches.threads = threads;
+    }
+
+    let save = |spec: &AgentSpec| {
+        let json = serde_json::to_string_pretty(spec).unwrap();
+        if let Err(e) = std::fs::write(&args.out, json) {
+            eprintln!("Failed to write {}: {e}", args.out);
+        }
+    };
+    let result = tune_weights(&base, &config, |spec, name, score| {
+        println!("Changed {name}: scored {:.0}%", score * 100.0);
+        save(spec);
+    });
+    match result {
+        Ok(best) => {
+            save(&best);
+            println!("Wrote {}", args.out);
+        }
+        Err(e) => eprintln!("Error: {e}"),
+    }
+}
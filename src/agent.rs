@@ -1,21 +1,353 @@
 mod common;
+mod external;
+mod fair;
 mod greedy;
+mod ismcts;
+mod linear;
 mod random;
+mod rival;
 mod turn_planner;
 
-pub use crate::agent::common::Agent;
+pub use crate::agent::common::{Agent, SearchBudget, legal_actions};
+pub use crate::agent::fair::{FairAdapter, FairAgent, SampledAgent};
+pub use crate::agent::linear::{
+    DEFAULT_VALUE_WEIGHTS, NUM_VALUE_FEATURES, TdConfig, train_td,
+};
+pub use crate::agent::rival::RivalWeights;
+pub use crate::agent::turn_planner::EvalWeights;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
 
+/// Serializable description of an agent and its parameters, e.g.
+/// `{"kind": "static", "cost_exponent": 2}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AgentSpec {
+    // Random (valid) actions.
+    Random,
+    // Very simple heuristics.
+    Greedy,
+    // Plans out all moves in a single turn, scoring hexes by node.cost^exp.
+    Static {
+        #[serde(default)]
+        cost_exponent: i32,
+        #[serde(default = "default_planner_budget")]
+        budget: SearchBudget,
+        #[serde(default)]
+        weights: EvalWeights,
+    },
+    // Considers upcoming terrain when planning.
+    Dynamic {
+        #[serde(default = "default_planner_budget")]
+        budget: SearchBudget,
+        #[serde(default)]
+        weights: EvalWeights,
+    },
+    // Dynamic planning that also weighs opponents: blocking them, racing
+    // them for shop cards, and rushing when they're close to finishing.
+    Rival {
+        #[serde(default = "default_planner_budget")]
+        budget: SearchBudget,
+        #[serde(default)]
+        weights: EvalWeights,
+        #[serde(default)]
+        rival: RivalWeights,
+    },
+    // Plans like the others, scoring states with a learned linear value
+    // function (see train_td).
+    Linear {
+        #[serde(default = "default_value_weights")]
+        weights: Vec<f64>,
+        // A file with learned weights to use instead: a linear agent spec,
+        // as saved by the train_td example, or a plain JSON array.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        weights_file: Option<String>,
+        #[serde(default = "default_planner_budget")]
+        budget: SearchBudget,
+    },
+    // Monte Carlo tree search over sampled hidden information.
+    Ismcts {
+        #[serde(default = "default_ismcts_budget")]
+        budget: SearchBudget,
+        #[serde(default = "default_rollout_rounds")]
+        rollout_rounds: usize,
+    },
+    // Another agent, shown game states sampled from its player's view
+    // instead of the real game, so it can't use hidden information.
+    Fair {
+        agent: Box<AgentSpec>,
+    },
+    // A bot running in a subprocess, e.g. {"command": ["python3", "bot.py"]}.
+    // The fallback agent plays if the bot fails or times out.
+    External {
+        command: Vec<String>,
+        #[serde(default = "default_external_timeout_ms")]
+        timeout_ms: u64,
+        #[serde(default = "default_external_fallback")]
+        fallback: Box<AgentSpec>,
+    },
+}
+
+/// The most search an agent from the API may do for each action.
+pub const MAX_API_BUDGET: SearchBudget = SearchBudget {
+    max_iterations: Some(100_000),
+    max_time_ms: Some(5_000),
+};
+
+fn default_planner_budget() -> SearchBudget {
+    SearchBudget {
+        max_iterations: Some(20_000),
+        max_time_ms: Some(1_000),
+    }
+}
+/// Where difficulty level 8 looks for learned linear value weights: the
+/// train_td example's default output, in the working directory.
+pub const LEARNED_WEIGHTS_FILE: &str = "linear.json";
+
+fn default_value_weights() -> Vec<f64> {
+    DEFAULT_VALUE_WEIGHTS.to_vec()
+}
+fn default_external_timeout_ms() -> u64 {
+    5_000
+}
+fn default_external_fallback() -> Box<AgentSpec> {
+    Box::new(AgentSpec::Greedy)
+}
+fn default_ismcts_budget() -> SearchBudget {
+    SearchBudget::iterations(200)
+}
+fn default_rollout_rounds() -> usize {
+    2
+}
+
+impl AgentSpec {
+    /// Spec for a numbered difficulty level, as used by create_agent.
+    pub fn from_difficulty(difficulty: usize) -> Self {
+        match difficulty {
+            0 => AgentSpec::Random,
+            1 => AgentSpec::Greedy,
+            2..=4 => AgentSpec::Static {
+                cost_exponent: difficulty as i32 - 2,
+                budget: default_planner_budget(),
+                weights: EvalWeights::default(),
+            },
+            5 => AgentSpec::Dynamic {
+                budget: default_planner_budget(),
+                weights: EvalWeights::default(),
+            },
+            6 => AgentSpec::Ismcts {
+                budget: default_ismcts_budget(),
+                rollout_rounds: default_rollout_rounds(),
+            },
+            7 => AgentSpec::Rival {
+                budget: default_planner_budget(),
+                weights: EvalWeights::default(),
+                rival: RivalWeights::default(),
+            },
+            8 => AgentSpec::Linear {
+                weights: default_value_weights(),
+                weights_file: Some(LEARNED_WEIGHTS_FILE.into()),
+                budget: default_planner_budget(),
+            },
+            // Unknown levels get the cheap dynamic planner.
+            _ => AgentSpec::Dynamic {
+                budget: default_planner_budget(),
+                weights: EvalWeights::default(),
+            },
+        }
+    }
+    /// Spec for an API player level:
+    ///   0 => Greedy
+    ///   1 => Static (cost_exponent = 1)
+    ///   2 => Dynamic
+    pub fn from_api_level(level: u8) -> Result<Self, String> {
+        match level {
+            0 => Ok(AgentSpec::Greedy),
+            1 => Ok(AgentSpec::Static {
+                cost_exponent: 1,
+                budget: default_planner_budget(),
+                weights: EvalWeights::default(),
+            }),
+            2 => Ok(AgentSpec::Dynamic {
+                budget: default_planner_budget(),
+                weights: EvalWeights::default(),
+            }),
+            _ => Err(format!("Invalid AI level {level}, expected 0..=2")),
+        }
+    }
+    /// Check a spec from an API client, capping its search budgets at
+    /// MAX_API_BUDGET. External agents run local commands, so they're only
+    /// allowed in local configs, never from the API. Returns the spec to use.
+    pub fn for_api(&self) -> Result<Self, String> {
+        let mut spec = self.clone();
+        match &mut spec {
+            AgentSpec::Random | AgentSpec::Greedy => {}
+            AgentSpec::Static { budget, .. }
+            | AgentSpec::Dynamic { budget, .. }
+            | AgentSpec::Rival { budget, .. }
+            | AgentSpec::Ismcts { budget, .. } => {
+                *budget = budget.capped(&MAX_API_BUDGET);
+            }
+            AgentSpec::Linear {
+                weights,
+                weights_file,
+                budget,
+            } => {
+                if weights_file.is_some() {
+                    return Err(
+                        "Weights files can't be used via the API".into()
+                    );
+                }
+                check_value_weights(weights)?;
+                *budget = budget.capped(&MAX_API_BUDGET);
+            }
+            AgentSpec::Fair { agent } => {
+                if let AgentSpec::Fair { .. } = **agent {
+                    return Err("Fair agents can't be nested".into());
+                }
+                **agent = agent.for_api()?;
+            }
+            AgentSpec::External { .. } => {
+                return Err("External agents can't be used via the API".into());
+            }
+        }
+        Ok(spec)
+    }
+    /// Read a spec from a JSON file, such as one written by a trainer.
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+    /// The evaluation weights, for agents that have them.
+    pub fn weights_mut(&mut self) -> Option<&mut EvalWeights> {
+        match self {
+            AgentSpec::Static { weights, .. }
+            | AgentSpec::Dynamic { weights, .. }
+            | AgentSpec::Rival { weights, .. } => Some(weights),
+            _ => None,
+        }
+    }
+    pub fn create(&self) -> Box<dyn Agent + Send> {
+        match self {
+            AgentSpec::Random => Box::<random::RandomAgent>::default(),
+            AgentSpec::Greedy => Box::<greedy::GreedyAgent>::default(),
+            AgentSpec::Static {
+                cost_exponent,
+                budget,
+                weights,
+            } => Box::new(turn_planner::StaticDistanceTurnPlanner::new(
+                *cost_exponent,
+                *budget,
+                weights.clone(),
+            )),
+            AgentSpec::Dynamic { budget, weights } => {
+                Box::new(turn_planner::DynamicCostTurnPlanner {
+                    budget: *budget,
+                    weights: weights.clone(),
+                })
+            }
+            AgentSpec::Rival {
+                budget,
+                weights,
+                rival,
+            } => Box::new(rival::RivalTurnPlanner {
+                budget: *budget,
+                weights: weights.clone(),
+                rival: rival.clone(),
+            }),
+            AgentSpec::Linear {
+                weights,
+                weights_file,
+                budget,
+            } => {
+                let mut agent =
+                    linear::LinearValueAgent::new(weights.clone(), *budget);
+                if let Some(path) = weights_file {
+                    match load_value_weights(path) {
+                        Ok(weights) => agent.weights = weights,
+                        Err(e) => agent.report(format!(
+                            "Failed to load weights from {path}, using the \
+                             spec's weights: {e}"
+                        )),
+                    }
+                }
+                if let Err(e) = check_value_weights(&agent.weights) {
+                    agent.report(format!("{e}, using the default weights"));
+                    agent.weights = default_value_weights();
+                }
+                Box::new(agent)
+            }
+            AgentSpec::Ismcts {
+                budget,
+                rollout_rounds,
+            } => Box::new(ismcts::IsmctsAgent::new(*budget, *rollout_rounds)),
+            AgentSpec::Fair { agent } => {
+                Box::new(FairAdapter::new(SampledAgent::new(agent.create())))
+            }
+            AgentSpec::External {
+                command,
+                timeout_ms,
+                fallback,
+            } => Box::new(external::ExternalAgent::new(
+                command.clone(),
+                std::time::Duration::from_millis(*timeout_ms),
+                fallback.create(),
+            )),
+        }
+    }
+}
+
+/// Create an agent for a difficulty level (see AgentSpec::from_difficulty).
+/// Level 8 plays with learned weights from LEARNED_WEIGHTS_FILE. Other agents,
+/// such as one with learned weights from another file, are created from their
+/// spec.
 pub fn create_agent(difficulty: usize) -> Box<dyn Agent + Send> {
-    match difficulty {
-        // Random (valid) actions.
-        0 => Box::<random::RandomAgent>::default(),
-        // Very simple heuristics.
-        1 => Box::<greedy::GreedyAgent>::default(),
-        // Plans out all moves in a single turn.
-        2 => Box::new(turn_planner::StaticDistanceTurnPlanner::new(0)),
-        3 => Box::new(turn_planner::StaticDistanceTurnPlanner::new(1)),
-        4 => Box::new(turn_planner::StaticDistanceTurnPlanner::new(2)),
-        // Considers upcoming terrain when planning.
-        _ => Box::<turn_planner::DynamicCostTurnPlanner>::default(),
+    AgentSpec::from_difficulty(difficulty).create()
+}
+
+/// Read learned linear value weights (see `AgentSpec::Linear`).
+pub fn load_value_weights(path: &str) -> Result<Vec<f64>, Box<dyn Error>> {
+    let json = std::fs::read_to_string(path)?;
+    let weights = match serde_json::from_str(&json) {
+        Ok(AgentSpec::Linear { weights, .. }) => weights,
+        Ok(_) => return Err(format!("{path} is not a linear agent").into()),
+        Err(_) => serde_json::from_str::<Vec<f64>>(&json)?,
+    };
+    check_value_weights(&weights)?;
+    Ok(weights)
+}
+
+// Linear value weights must match the features, one each.
+fn check_value_weights(weights: &[f64]) -> Result<(), String> {
+    if weights.len() != NUM_VALUE_FEATURES {
+        return Err(format!(
+            "Linear agents need {NUM_VALUE_FEATURES} weights, got {}",
+            weights.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Create an agent from a spec file, e.g. learned weights saved as
+/// `{"kind": "linear", "weights": [...]}`.
+pub fn load_agent(path: &str) -> Result<Box<dyn Agent + Send>, Box<dyn Error>> {
+    Ok(AgentSpec::load(path)?.create())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn difficulty_levels() {
+        let level = AgentSpec::from_difficulty;
+        assert!(matches!(level(0), AgentSpec::Random));
+        assert!(matches!(level(5), AgentSpec::Dynamic { .. }));
+        assert!(matches!(level(6), AgentSpec::Ismcts { .. }));
+        assert!(matches!(level(7), AgentSpec::Rival { .. }));
+        // Out-of-range levels don't run an expensive search.
+        assert!(matches!(level(8), AgentSpec::Linear { .. }));
+        assert!(matches!(level(9), AgentSpec::Dynamic { .. }));
+        assert!(matches!(level(100), AgentSpec::Dynamic { .. }));
     }
 }
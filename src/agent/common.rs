@@ -1,20 +1,126 @@
 use crate::cards::{Card, CardAction};
 use crate::data::{BonusToken, HexDirection, Terrain};
 use crate::game::{
-    BuyCardAction, BuyIndex, DrawAction, GameState, MoveAction, PlayerAction,
+    ActionOutcome, BuyCardAction, BuyIndex, DrawAction, GameState, MoveAction,
+    PlayerAction,
 };
 use crate::player::Player;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::time::Instant;
 
+/// A player that chooses actions. Agents are notified of each game event, so
+/// they can keep state between calls (e.g. plans or opponent models).
 pub trait Agent {
+    /// Called once before the agent's first action, with its seat index.
+    fn on_game_start(&mut self, _game: &GameState, _player_idx: usize) {}
     fn choose_action(
-        &self,
+        &mut self,
         game: &GameState,
         rng: &mut dyn rand::RngCore,
     ) -> PlayerAction;
+    /// Called after any player's action has been processed.
+    fn on_action(
+        &mut self,
+        _game: &GameState,
+        _player_idx: usize,
+        _action: &PlayerAction,
+        _outcome: &ActionOutcome,
+    ) {
+    }
+    /// Called once the game is over.
+    fn on_game_end(&mut self, _game: &GameState) {}
+    /// Problems the agent worked around since the last call, e.g. falling
+    /// back to a simpler choice, for the caller to log.
+    fn take_errors(&mut self) -> Vec<String> {
+        vec![]
+    }
+}
+
+/// Limits on how much work a search may do before returning its best action.
+/// Unset limits are unbounded.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SearchBudget {
+    // Maximum number of search iterations (or simulations).
+    pub max_iterations: Option<usize>,
+    // Maximum wall-clock time per decision, in milliseconds.
+    pub max_time_ms: Option<u64>,
+}
+impl SearchBudget {
+    pub fn iterations(n: usize) -> Self {
+        Self {
+            max_iterations: Some(n),
+            max_time_ms: None,
+        }
+    }
+    /// This budget with each limit capped at `max`'s, where `max` has one.
+    pub fn capped(&self, max: &SearchBudget) -> Self {
+        fn cap<T: Ord>(limit: Option<T>, max: Option<T>) -> Option<T> {
+            match (limit, max) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        Self {
+            max_iterations: cap(self.max_iterations, max.max_iterations),
+            max_time_ms: cap(self.max_time_ms, max.max_time_ms),
+        }
+    }
+    /// Start tracking a search against this budget.
+    pub(super) fn start(&self) -> BudgetTracker {
+        BudgetTracker {
+            deadline: self.max_time_ms.map(|ms| {
+                Instant::now() + std::time::Duration::from_millis(ms)
+            }),
+            max_iterations: self.max_iterations,
+        }
+    }
+}
+
+pub(super) struct BudgetTracker {
+    deadline: Option<Instant>,
+    max_iterations: Option<usize>,
+}
+impl BudgetTracker {
+    /// Has the search used up its budget after `iterations` iterations?
+    pub(super) fn exhausted(&self, iterations: usize) -> bool {
+        self.max_iterations.is_some_and(|n| iterations >= n)
+            || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+}
+
+/// Actions the current player could take, including draws and ending the
+/// turn. Trashing and discarding are limited to a few sensible choices.
+pub fn legal_actions(game: &GameState) -> Vec<PlayerAction> {
+    let mut actions: Vec<PlayerAction> = valid_draw_actions(game)
+        .into_iter()
+        .map(PlayerAction::Draw)
+        .collect();
+    actions
+        .extend(valid_move_actions(game).into_iter().map(PlayerAction::Move));
+    actions.extend(
+        valid_buy_actions(game)
+            .into_iter()
+            .map(PlayerAction::BuyCard),
+    );
+    let me = game.curr_player();
+    if can_safely_trash(me) {
+        for i in 0..me.hand.len() {
+            actions.push(PlayerAction::Trash(vec![i]));
+        }
+    }
+    if !me.hand.is_empty() {
+        actions.push(PlayerAction::Discard((0..me.hand.len()).collect()));
+    }
+    actions.push(PlayerAction::FinishTurn);
+    actions
 }
 
 pub(super) fn can_safely_trash(me: &Player) -> bool {
+    if me.trashes > 0 && me.hand.iter().any(|c| c.is_curse()) {
+        return true;
+    }
     me.trashes > 0
         && !me.hand.is_empty()
         && me.num_cards() > 4
@@ -183,13 +289,11 @@ pub(super) fn valid_draw_actions(game: &GameState) -> Vec<DrawAction> {
     me.hand
         .iter()
         .enumerate()
-        .filter_map(|(i, c)| match c.action {
-            Some(CardAction::Draw(_)) | Some(CardAction::DrawAndTrash(_)) => {
-                Some(DrawAction {
-                    card: Some(i),
-                    token: if c.single_use { double_use } else { None },
-                })
-            }
+        .filter_map(|(i, c)| match &c.action {
+            Some(act) if act.is_playable() => Some(DrawAction {
+                card: Some(i),
+                token: if c.single_use { double_use } else { None },
+            }),
             _ => None,
         })
         .chain(me.tokens.iter().enumerate().filter_map(|(i, t)| match t {
@@ -518,6 +622,7 @@ fn all_moves_helper(
 #[test]
 fn test_all_moves_helper() {
     use crate::data::{AxialCoord, HexMap, LayoutInfo};
+    use crate::rules::RuleSet;
     use assert_matches::assert_matches;
 
     // cargo run --example render_board -- -f svg --layout='B,0,0,0;Z,0,4,-4' | display
@@ -529,7 +634,7 @@ fn test_all_moves_helper() {
     // Bottom left hex of the map.
     let pos = AxialCoord { q: -3, r: 3 };
     let my_idx = map.node_idx(pos).unwrap();
-    let players = vec![Player::new(pos, &mut rand::rng())];
+    let players = vec![Player::new(pos, &RuleSet::default(), &mut rand::rng())];
     let game = GameState::from_parts(map, players, 0);
 
     // No movement => no moves.
@@ -581,6 +686,7 @@ fn test_finds_path() {
     // S A E
     //  . B
     use crate::data::{AxialCoord, HexMap, Node};
+    use crate::rules::RuleSet;
     let j1 = Node {
         terrain: Terrain::Jungle,
         cost: 1,
@@ -613,7 +719,7 @@ fn test_finds_path() {
     let pos = AxialCoord { q: 0, r: 0 };
     let my_idx = map.node_idx(pos).unwrap();
     assert_eq!(my_idx, 0);
-    let players = vec![Player::new(pos, &mut rand::rng())];
+    let players = vec![Player::new(pos, &RuleSet::default(), &mut rand::rng())];
     let game = GameState::from_parts(map, players, 0);
 
     let seen = all_moves_helper(&[4, 0, 0], &game, my_idx, None);
@@ -628,6 +734,7 @@ fn test_finds_path() {
 #[test]
 fn test_breaks_barrier() {
     use crate::data::{AxialCoord, Barrier, HexMap};
+    use crate::rules::RuleSet;
     // Check that barrier-breaking is handled correctly.
     // start -> A(1) -> barrier(2) -> B(1) = 4 cost
     // S A | B
@@ -643,14 +750,14 @@ fn test_breaks_barrier() {
     let pos = AxialCoord { q: 0, r: 0 };
     let my_idx = map.node_idx(pos).unwrap();
     assert_eq!(my_idx, 0);
-    let players = vec![Player::new(pos, &mut rand::rng())];
+    let players = vec![Player::new(pos, &RuleSet::default(), &mut rand::rng())];
     let mut game = GameState::from_parts(map, players, 0);
     game.barriers.push(Barrier {
         from_board: 0,
         to_board: 1,
         terrain: Terrain::Jungle,
         cost: 2,
-        edges: vec![],
+        edges: std::sync::Arc::new([]),
     });
 
     let seen = all_moves_helper(&[4, 0, 0], &game, my_idx, None);
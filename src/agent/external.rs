@@ -0,0 +1,422 @@
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::time::{Duration, Instant};
+
+use crate::agent::common::*;
+use crate::game::{ActionOutcome, GameState, PlayerAction};
+use crate::view::PlayerView;
+
+const PROTOCOL_VERSION: u32 = 1;
+
+/// Messages sent to the bot, one JSON object per line.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ToBot<'a> {
+    // Sent once at startup. The bot must reply with "ready".
+    Hello {
+        protocol: u32,
+        player_idx: usize,
+        num_players: usize,
+    },
+    // Our turn: the bot must reply with an "action" echoing the same id.
+    Turn {
+        id: u64,
+        view: &'a PlayerView,
+        legal_actions: &'a [PlayerAction],
+    },
+    // Any player's action was processed. No reply expected.
+    Action {
+        player_idx: usize,
+        action: &'a PlayerAction,
+    },
+    // The game is over. No reply expected, and the bot should exit.
+    GameOver {
+        view: &'a PlayerView,
+    },
+}
+
+/// Messages read from the bot.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FromBot {
+    Ready {
+        #[serde(default)]
+        name: String,
+    },
+    Action {
+        id: u64,
+        action: PlayerAction,
+    },
+}
+
+struct BotProcess {
+    child: Child,
+    // Lines for the bot's stdin, written on a separate thread so that a bot
+    // that stops reading can't block us.
+    writer: SyncSender<String>,
+    // The result of each write, in order.
+    written: Receiver<Result<(), String>>,
+    // Lines from the bot's stdout, read on a separate thread.
+    lines: Receiver<String>,
+}
+
+/// An agent played by a local subprocess, speaking line-based JSON over
+/// stdin/stdout (see `ToBot` and `FromBot`). The legal actions sent each turn
+/// are a convenience: the bot may reply with any action the rules allow.
+/// If the bot can't be started, fails the handshake, times out, or replies
+/// with an invalid action, the fallback agent chooses instead.
+pub(super) struct ExternalAgent {
+    command: Vec<String>,
+    // Name for messages: the bot's own name once known, else the command.
+    name: String,
+    timeout: Duration,
+    fallback: Box<dyn Agent + Send>,
+    // None until started, or after the bot fails.
+    process: Option<BotProcess>,
+    started: bool,
+    player_idx: Option<usize>,
+    next_id: u64,
+    // Failures since the last take_errors call.
+    errors: Vec<String>,
+}
+
+impl ExternalAgent {
+    pub(super) fn new(
+        command: Vec<String>,
+        timeout: Duration,
+        fallback: Box<dyn Agent + Send>,
+    ) -> Self {
+        Self {
+            name: command.join(" "),
+            command,
+            timeout,
+            fallback,
+            process: None,
+            started: false,
+            player_idx: None,
+            next_id: 0,
+            errors: vec![],
+        }
+    }
+
+    // Launch the bot and wait for it to be ready.
+    fn start(&mut self, game: &GameState, player_idx: usize) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        self.player_idx = Some(player_idx);
+        match self.launch(game, player_idx) {
+            Ok((process, name)) => {
+                self.process = Some(process);
+                if !name.is_empty() {
+                    self.name = name;
+                }
+            }
+            Err(e) => self.fail(&e),
+        }
+    }
+
+    fn launch(
+        &self,
+        game: &GameState,
+        player_idx: usize,
+    ) -> Result<(BotProcess, String), String> {
+        let (program, args) =
+            self.command.split_first().ok_or("Empty bot command")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start: {e}"))?;
+        let mut stdin = child.stdin.take().unwrap();
+        let (writer, to_write) = mpsc::sync_channel::<String>(1);
+        let (ack, written) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in to_write {
+                let result = writeln!(stdin, "{line}")
+                    .and_then(|_| stdin.flush())
+                    .map_err(|e| format!("Failed to write: {e}"));
+                let failed = result.is_err();
+                if ack.send(result).is_err() || failed {
+                    break;
+                }
+            }
+        });
+        let stdout = child.stdout.take().unwrap();
+        let (tx, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut process = BotProcess {
+            child,
+            writer,
+            written,
+            lines,
+        };
+        let deadline = Instant::now() + self.timeout;
+        let hello = ToBot::Hello {
+            protocol: PROTOCOL_VERSION,
+            player_idx,
+            num_players: game.players.len(),
+        };
+        let reply = process
+            .send(&hello, deadline)
+            .and_then(|_| process.receive(deadline));
+        match reply {
+            Ok(FromBot::Ready { name }) => Ok((process, name)),
+            Ok(_) => {
+                process.stop();
+                Err("Expected a ready message".into())
+            }
+            Err(e) => {
+                process.stop();
+                Err(e)
+            }
+        }
+    }
+
+    // Stop using the bot for the rest of the game.
+    fn fail(&mut self, err: &str) {
+        self.errors.push(format!(
+            "Bot {:?} failed, using fallback agent: {err}",
+            self.name
+        ));
+        if let Some(process) = self.process.take() {
+            process.stop();
+        }
+    }
+
+    fn ask_bot(&mut self, game: &GameState) -> Result<PlayerAction, String> {
+        let Some(process) = &mut self.process else {
+            return Err("Bot is not running".into());
+        };
+        let me = self.player_idx.unwrap_or(game.curr_player_idx);
+        let id = self.next_id;
+        self.next_id += 1;
+        let deadline = Instant::now() + self.timeout;
+        let turn = ToBot::Turn {
+            id,
+            view: &PlayerView::new(game, me, &[]),
+            legal_actions: &legal_actions(game),
+        };
+        // A bot that can't take its input is as good as dead.
+        if let Err(e) = process.send(&turn, deadline) {
+            self.fail(&e);
+            return Err(e);
+        }
+        loop {
+            match process.receive(deadline)? {
+                // Ignore late replies to earlier turns.
+                FromBot::Action { id: reply_id, .. } if reply_id != id => {}
+                FromBot::Action { action, .. } => {
+                    // Check the action against the rules on a copy.
+                    let mut check = game.clone();
+                    check.process_action(&action, &mut rand::rng())?;
+                    return Ok(action);
+                }
+                FromBot::Ready { .. } => {}
+            }
+        }
+    }
+}
+
+impl BotProcess {
+    fn send(&mut self, msg: &ToBot, deadline: Instant) -> Result<(), String> {
+        let line = serde_json::to_string(msg).map_err(|e| e.to_string())?;
+        self.writer.try_send(line).map_err(|e| match e {
+            mpsc::TrySendError::Full(_) => "Timed out writing".to_string(),
+            mpsc::TrySendError::Disconnected(_) => "Bot exited".to_string(),
+        })?;
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        self.written.recv_timeout(timeout).map_err(|e| match e {
+            mpsc::RecvTimeoutError::Timeout => "Timed out writing".to_string(),
+            mpsc::RecvTimeoutError::Disconnected => "Bot exited".to_string(),
+        })?
+    }
+
+    fn receive(&mut self, deadline: Instant) -> Result<FromBot, String> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let line = self.lines.recv_timeout(timeout).map_err(|e| match e {
+            mpsc::RecvTimeoutError::Timeout => "Timed out".to_string(),
+            mpsc::RecvTimeoutError::Disconnected => "Bot exited".to_string(),
+        })?;
+        serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid message {line:?}: {e}"))
+    }
+
+    // Kill the bot, which also ends a write stuck on its full stdin.
+    fn stop(mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Agent for ExternalAgent {
+    fn on_game_start(&mut self, game: &GameState, player_idx: usize) {
+        self.start(game, player_idx);
+        self.fallback.on_game_start(game, player_idx);
+    }
+    fn choose_action(
+        &mut self,
+        game: &GameState,
+        rng: &mut dyn RngCore,
+    ) -> PlayerAction {
+        self.start(game, game.curr_player_idx);
+        match self.ask_bot(game) {
+            Ok(action) => action,
+            Err(e) => {
+                // A dead bot stays dead, but a single bad reply is forgiven.
+                let exited = self
+                    .process
+                    .as_mut()
+                    .is_some_and(|p| !matches!(p.child.try_wait(), Ok(None)));
+                if exited {
+                    self.fail(&e);
+                } else if self.process.is_some() {
+                    self.errors.push(format!("Bot {:?}: {e}", self.name));
+                }
+                self.fallback.choose_action(game, rng)
+            }
+        }
+    }
+    fn on_action(
+        &mut self,
+        game: &GameState,
+        player_idx: usize,
+        action: &PlayerAction,
+        outcome: &ActionOutcome,
+    ) {
+        let deadline = Instant::now() + self.timeout;
+        if let Some(process) = &mut self.process
+            && let Err(e) =
+                process.send(&ToBot::Action { player_idx, action }, deadline)
+        {
+            self.fail(&e);
+        }
+        self.fallback.on_action(game, player_idx, action, outcome);
+    }
+    fn on_game_end(&mut self, game: &GameState) {
+        if let Some(mut process) = self.process.take() {
+            let me = self.player_idx.unwrap_or(0);
+            let view = PlayerView::new(game, me, &[]);
+            // Best effort: don't wait for the bot to read it.
+            let _ =
+                process.send(&ToBot::GameOver { view: &view }, Instant::now());
+            // Close stdin and stop the bot, rather than blocking the caller
+            // while it exits on its own.
+            drop(process.writer);
+            let _ = process.child.kill();
+            let _ = process.child.wait();
+        }
+        self.fallback.on_game_end(game);
+    }
+    fn take_errors(&mut self) -> Vec<String> {
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.extend(self.fallback.take_errors());
+        errors
+    }
+}
+
+impl Drop for ExternalAgent {
+    fn drop(&mut self) {
+        if let Some(process) = self.process.take() {
+            process.stop();
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::agent::greedy::GreedyAgent;
+    use crate::rules::RuleSet;
+
+    fn shell_bot(script: &str) -> ExternalAgent {
+        ExternalAgent::new(
+            vec!["sh".into(), "-c".into(), script.into()],
+            Duration::from_millis(500),
+            Box::<GreedyAgent>::default(),
+        )
+    }
+
+    #[test]
+    fn external_bots() {
+        let rng = &mut rand::rng();
+        let game =
+            GameState::new(2, "first", &RuleSet::default(), rng).unwrap();
+        // A bot that always ends its turn.
+        let mut bot = shell_bot(
+            r#"read hello; echo '{"type": "ready", "name": "sleepy"}'
+            while read line; do
+                id=$(echo "$line" | sed -n 's/^{"type":"turn","id":\([0-9]*\).*/\1/p')
+                [ -n "$id" ] && echo "{\"type\": \"action\", \"id\": $id, \"action\": \"FinishTurn\"}"
+            done"#,
+        );
+        bot.on_game_start(&game, 0);
+        assert!(bot.process.is_some());
+        assert_eq!(bot.name, "sleepy");
+        let action = bot.choose_action(&game, rng);
+        assert!(matches!(action, PlayerAction::FinishTurn));
+        // Ending the game doesn't wait for the bot to exit.
+        let start = Instant::now();
+        bot.on_game_end(&game);
+        assert!(start.elapsed() < bot.timeout);
+        assert!(bot.take_errors().is_empty());
+
+        // Bots that fail fall back to the built-in agent.
+        let mut bot = shell_bot("exit 1");
+        bot.on_game_start(&game, 0);
+        assert!(bot.process.is_none());
+        assert_eq!(bot.take_errors().len(), 1);
+        let mut check = game.clone();
+        let action = bot.choose_action(&check, rng);
+        check.process_action(&action, rng).unwrap();
+
+        // So do illegal replies.
+        let mut bot = shell_bot(
+            r#"read hello; echo '{"type": "ready"}'
+            while read line; do
+                echo '{"type": "action", "id": 0, "action": {"Trash": [0]}}'
+            done"#,
+        );
+        let mut check = game.clone();
+        let action = bot.choose_action(&check, rng);
+        check.process_action(&action, rng).unwrap();
+    }
+
+    #[test]
+    fn external_bot_not_reading() {
+        let rng = &mut rand::rng();
+        let game =
+            GameState::new(2, "first", &RuleSet::default(), rng).unwrap();
+        // A bot that never reads its input, so the pipe eventually fills up.
+        let mut bot = shell_bot(r#"echo '{"type": "ready"}'; exec sleep 60"#);
+        bot.on_game_start(&game, 0);
+        assert!(bot.process.is_some());
+        let start = Instant::now();
+        let action = PlayerAction::FinishTurn;
+        for _ in 0..100_000 {
+            bot.on_action(&game, 1, &action, &ActionOutcome::Ok);
+            if bot.process.is_none() {
+                break;
+            }
+        }
+        assert!(bot.process.is_none());
+        assert!(start.elapsed() < 2 * bot.timeout);
+        assert_eq!(bot.take_errors().len(), 1);
+        // The fallback agent takes over.
+        let mut check = game.clone();
+        let action = bot.choose_action(&check, rng);
+        check.process_action(&action, rng).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+use rand::RngCore;
+
+use crate::agent::common::Agent;
+use crate::game::{ActionOutcome, GameState, PlayerAction};
+use crate::view::{PlayerView, ViewSampler};
+
+/// An agent that only sees what its player could see. Unlike `Agent`, it
+/// can't peek at hidden information, and it can run on the client side of
+/// the API using the views sent to players.
+pub trait FairAgent {
+    /// Called once before the agent's first action.
+    fn on_game_start(&mut self, _view: &PlayerView) {}
+    fn choose_action(
+        &mut self,
+        view: &PlayerView,
+        rng: &mut dyn RngCore,
+    ) -> PlayerAction;
+    /// Called after any player's action has been processed.
+    fn on_action(
+        &mut self,
+        _view: &PlayerView,
+        _player_idx: usize,
+        _action: &PlayerAction,
+        _outcome: &ActionOutcome,
+    ) {
+    }
+    /// Called once the game is over.
+    fn on_game_end(&mut self, _view: &PlayerView) {}
+    /// Problems the agent worked around since the last call (see
+    /// `Agent::take_errors`).
+    fn take_errors(&mut self) -> Vec<String> {
+        vec![]
+    }
+}
+
+/// Runs a `FairAgent` as an `Agent`, showing it only its player's view.
+pub struct FairAdapter<A> {
+    agent: A,
+    // Our seat, once known.
+    player_idx: Option<usize>,
+}
+impl<A: FairAgent> FairAdapter<A> {
+    pub fn new(agent: A) -> Self {
+        Self {
+            agent,
+            player_idx: None,
+        }
+    }
+}
+impl<A: FairAgent> Agent for FairAdapter<A> {
+    fn on_game_start(&mut self, game: &GameState, player_idx: usize) {
+        self.player_idx = Some(player_idx);
+        self.agent
+            .on_game_start(&PlayerView::new(game, player_idx, &[]));
+    }
+    fn choose_action(
+        &mut self,
+        game: &GameState,
+        rng: &mut dyn RngCore,
+    ) -> PlayerAction {
+        // We're only asked to act on our own turn.
+        let me = *self.player_idx.get_or_insert(game.curr_player_idx);
+        self.agent
+            .choose_action(&PlayerView::new(game, me, &[]), rng)
+    }
+    fn on_action(
+        &mut self,
+        game: &GameState,
+        player_idx: usize,
+        action: &PlayerAction,
+        outcome: &ActionOutcome,
+    ) {
+        if let Some(me) = self.player_idx {
+            let view = PlayerView::new(game, me, &[]);
+            self.agent.on_action(&view, player_idx, action, outcome);
+        }
+    }
+    fn on_game_end(&mut self, game: &GameState) {
+        if let Some(me) = self.player_idx {
+            self.agent.on_game_end(&PlayerView::new(game, me, &[]));
+        }
+    }
+    fn take_errors(&mut self) -> Vec<String> {
+        self.agent.take_errors()
+    }
+}
+
+/// Makes any `Agent` fair by showing it game states sampled from the view,
+/// instead of the real game.
+pub struct SampledAgent {
+    agent: Box<dyn Agent + Send>,
+    // Built from the first view, then updated in place for later views so
+    // the board is shared rather than rebuilt.
+    sampler: Option<ViewSampler>,
+    errors: Vec<String>,
+}
+impl SampledAgent {
+    pub fn new(agent: Box<dyn Agent + Send>) -> Self {
+        Self {
+            agent,
+            sampler: None,
+            errors: vec![],
+        }
+    }
+    fn sample(
+        &mut self,
+        view: &PlayerView,
+        rng: &mut dyn RngCore,
+    ) -> Result<GameState, String> {
+        let sampler = match &mut self.sampler {
+            Some(sampler) => {
+                sampler.update(view)?;
+                sampler
+            }
+            None => self.sampler.insert(ViewSampler::new(view)?),
+        };
+        Ok(sampler.sample(rng))
+    }
+    fn record_error(&mut self, err: String) {
+        self.errors
+            .push(format!("Failed to sample the game: {err}"));
+    }
+}
+impl FairAgent for SampledAgent {
+    fn on_game_start(&mut self, view: &PlayerView) {
+        let me = view.my_player.info.player_idx;
+        match ViewSampler::new(view) {
+            Ok(sampler) => {
+                let game = sampler.sample(&mut rand::rng());
+                self.sampler = Some(sampler);
+                self.agent.on_game_start(&game, me);
+            }
+            Err(e) => self.record_error(e),
+        }
+    }
+    fn choose_action(
+        &mut self,
+        view: &PlayerView,
+        rng: &mut dyn RngCore,
+    ) -> PlayerAction {
+        match self.sample(view, rng) {
+            Ok(game) => self.agent.choose_action(&game, rng),
+            Err(e) => {
+                // Ending the turn is always legal.
+                self.record_error(e);
+                PlayerAction::FinishTurn
+            }
+        }
+    }
+    fn on_action(
+        &mut self,
+        view: &PlayerView,
+        player_idx: usize,
+        action: &PlayerAction,
+        outcome: &ActionOutcome,
+    ) {
+        match self.sample(view, &mut rand::rng()) {
+            Ok(game) => {
+                self.agent.on_action(&game, player_idx, action, outcome)
+            }
+            Err(e) => self.record_error(e),
+        }
+    }
+    fn on_game_end(&mut self, view: &PlayerView) {
+        match self.sample(view, &mut rand::rng()) {
+            Ok(game) => self.agent.on_game_end(&game),
+            Err(e) => self.record_error(e),
+        }
+    }
+    fn take_errors(&mut self) -> Vec<String> {
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.extend(self.agent.take_errors());
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgentSpec;
+    use crate::game::GameState;
+    use crate::rules::RuleSet;
+
+    #[test]
+    fn fair_agents_play() {
+        let rng = &mut rand::rng();
+        let mut game =
+            GameState::new(2, "first", &RuleSet::default(), rng).unwrap();
+        let spec: AgentSpec = serde_json::from_str(
+            r#"{"kind": "fair", "agent": {"kind": "greedy"}}"#,
+        )
+        .unwrap();
+        let mut agents = [spec.create(), spec.create()];
+        for (i, agent) in agents.iter_mut().enumerate() {
+            agent.on_game_start(&game, i);
+        }
+        for _ in 0..30 {
+            let agent = &mut agents[game.curr_player_idx];
+            let action = agent.choose_action(&game, rng);
+            game.process_action(&action, rng).unwrap();
+        }
+        assert!(agents.iter_mut().all(|a| a.take_errors().is_empty()));
+    }
+
+    #[test]
+    fn sampling_errors_are_reported() {
+        let rng = &mut rand::rng();
+        let game =
+            GameState::new(2, "first", &RuleSet::default(), rng).unwrap();
+        let mut view = PlayerView::new(&game, 0, &[]);
+        view.my_player.info.deck_size += 1;
+        let mut agent = SampledAgent::new(AgentSpec::Greedy.create());
+        agent.on_game_start(&view);
+        let action = agent.choose_action(&view, rng);
+        assert!(matches!(action, PlayerAction::FinishTurn));
+        assert_eq!(agent.take_errors().len(), 2);
+        assert!(agent.take_errors().is_empty());
+    }
+}
@@ -10,7 +10,7 @@ use rand::{Rng, RngCore};
 pub(super) struct GreedyAgent {}
 impl Agent for GreedyAgent {
     fn choose_action(
-        &self,
+        &mut self,
         game: &GameState,
         rng: &mut dyn RngCore,
     ) -> PlayerAction {
@@ -56,7 +56,7 @@ impl Agent for GreedyAgent {
                 .iter()
                 .enumerate()
                 .filter_map(|(i, c)| {
-                    if c.movement.iter().sum::<u8>() == 1 {
+                    if c.is_curse() || c.movement.iter().sum::<u8>() == 1 {
                         Some(i)
                     } else {
                         None
@@ -236,7 +236,8 @@ fn best_move_for_node(
             hand.iter()
                 .enumerate()
                 .filter_map(|(i, card)| {
-                    if card.movement.iter().sum::<u8>() == 1 {
+                    if card.is_curse() || card.movement.iter().sum::<u8>() == 1
+                    {
                         Some(i)
                     } else {
                         None
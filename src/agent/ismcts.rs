@@ -0,0 +1,235 @@
+use rand::RngCore;
+use rand::prelude::IndexedRandom;
+
+use crate::agent::common::*;
+use crate::agent::greedy::GreedyAgent;
+use crate::game::{ActionOutcome, GameState, PlayerAction};
+
+// Exploration constant for UCB selection.
+const EXPLORATION: f64 = 0.7;
+// Maximum number of actions taken within the tree per iteration.
+const MAX_TREE_DEPTH: usize = 12;
+// Safety limit on the number of actions in a single rollout.
+const MAX_ROLLOUT_ACTIONS: usize = 500;
+
+/// Information-set MCTS: each iteration samples the hidden information
+/// (deck order, opponents' hands, cave tokens), then searches a single tree
+/// shared across all samples, using greedy rollouts to evaluate leaves.
+pub(super) struct IsmctsAgent {
+    budget: SearchBudget,
+    // Number of rounds to simulate past the tree before scoring.
+    rollout_rounds: usize,
+    rollout_policy: GreedyAgent,
+}
+impl IsmctsAgent {
+    pub(super) fn new(budget: SearchBudget, rollout_rounds: usize) -> Self {
+        // An unbounded search would never return.
+        let budget = if budget == SearchBudget::default() {
+            SearchBudget::iterations(200)
+        } else {
+            budget
+        };
+        Self {
+            budget,
+            rollout_rounds,
+            rollout_policy: GreedyAgent::default(),
+        }
+    }
+}
+
+struct TreeNode {
+    // Key of the action leading to this node, and who took it.
+    action_key: String,
+    player: usize,
+    visits: u32,
+    // How many times this node's action was legal when its parent was visited.
+    availability: u32,
+    total_reward: f64,
+    children: Vec<usize>,
+}
+impl TreeNode {
+    fn ucb(&self) -> f64 {
+        self.total_reward / self.visits as f64
+            + EXPLORATION
+                * ((self.availability as f64).ln() / self.visits as f64).sqrt()
+    }
+}
+
+// Actions are matched across determinizations by their debug representation.
+fn action_key(action: &PlayerAction) -> String {
+    format!("{action:?}")
+}
+
+// Rewards in [0, 1] for each player: wins count fully, otherwise compare
+// progress against the best opponent.
+fn rewards(game: &GameState) -> Vec<f64> {
+    let scores = game.player_scores();
+    let best = scores.iter().copied().max().unwrap_or(0);
+    (0..scores.len())
+        .map(|i| {
+            if game.end_reason.is_some() {
+                return if scores[i] == best { 1.0 } else { 0.0 };
+            }
+            let best_other = scores
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, &s)| s)
+                .max()
+                .unwrap_or(0);
+            let diff = (scores[i] - best_other) as f64;
+            1.0 / (1.0 + (-diff / 3.0).exp())
+        })
+        .collect()
+}
+
+impl IsmctsAgent {
+    fn run_iteration(
+        &mut self,
+        tree: &mut Vec<TreeNode>,
+        game: &GameState,
+        rng: &mut dyn RngCore,
+    ) {
+        let me = game.curr_player_idx;
+        let mut sim = game.determinize(me, rng);
+        let mut path = vec![0];
+        let mut finished = false;
+
+        // Selection and expansion.
+        for _ in 0..MAX_TREE_DEPTH {
+            let node_idx = *path.last().unwrap();
+            let actions = legal_actions(&sim);
+            let keys: Vec<String> = actions.iter().map(action_key).collect();
+            let children = tree[node_idx].children.clone();
+            for &c in &children {
+                if keys.contains(&tree[c].action_key) {
+                    tree[c].availability += 1;
+                }
+            }
+            let untried: Vec<usize> = (0..actions.len())
+                .filter(|&i| {
+                    !children.iter().any(|&c| tree[c].action_key == keys[i])
+                })
+                .collect();
+            let player = sim.curr_player_idx;
+            let (action_idx, child_idx) = if let Some(&i) = untried.choose(rng)
+            {
+                tree.push(TreeNode {
+                    action_key: keys[i].clone(),
+                    player,
+                    visits: 0,
+                    availability: 1,
+                    total_reward: 0.0,
+                    children: vec![],
+                });
+                let child_idx = tree.len() - 1;
+                tree[node_idx].children.push(child_idx);
+                (i, child_idx)
+            } else {
+                let child_idx = children
+                    .iter()
+                    .copied()
+                    .filter(|&c| keys.contains(&tree[c].action_key))
+                    .max_by(|&a, &b| tree[a].ucb().total_cmp(&tree[b].ucb()))
+                    .unwrap();
+                let key = &tree[child_idx].action_key;
+                (keys.iter().position(|k| k == key).unwrap(), child_idx)
+            };
+            path.push(child_idx);
+            match sim.process_action(&actions[action_idx], rng) {
+                Ok(ActionOutcome::GameOver) | Err(_) => {
+                    finished = true;
+                    break;
+                }
+                Ok(_) => {}
+            }
+            if untried.contains(&action_idx) {
+                break;
+            }
+        }
+
+        // Rollout.
+        if !finished {
+            let last_round = sim.round_idx + self.rollout_rounds;
+            for _ in 0..MAX_ROLLOUT_ACTIONS {
+                if sim.round_idx >= last_round {
+                    break;
+                }
+                let action = self.rollout_policy.choose_action(&sim, rng);
+                match sim.process_action(&action, rng) {
+                    Ok(ActionOutcome::GameOver) | Err(_) => break,
+                    Ok(_) => {}
+                }
+            }
+        }
+
+        // Backpropagation.
+        let rewards = rewards(&sim);
+        for &node_idx in &path {
+            let node = &mut tree[node_idx];
+            node.visits += 1;
+            node.total_reward += rewards[node.player];
+        }
+    }
+}
+
+impl Agent for IsmctsAgent {
+    fn choose_action(
+        &mut self,
+        game: &GameState,
+        rng: &mut dyn RngCore,
+    ) -> PlayerAction {
+        let mut actions = legal_actions(game);
+        if actions.len() == 1 {
+            return actions.pop().unwrap();
+        }
+        let mut tree = vec![TreeNode {
+            action_key: String::new(),
+            player: game.curr_player_idx,
+            visits: 0,
+            availability: 0,
+            total_reward: 0.0,
+            children: vec![],
+        }];
+        let tracker = self.budget.start();
+        let mut iterations = 0;
+        while !tracker.exhausted(iterations) {
+            self.run_iteration(&mut tree, game, rng);
+            iterations += 1;
+        }
+        // Pick the most visited action that is legal in the real game.
+        let best = tree[0]
+            .children
+            .iter()
+            .filter_map(|&c| {
+                let key = &tree[c].action_key;
+                actions
+                    .iter()
+                    .position(|a| &action_key(a) == key)
+                    .map(|i| (i, tree[c].visits))
+            })
+            .max_by_key(|&(_, visits)| visits);
+        match best {
+            Some((i, _)) => actions.swap_remove(i),
+            None => self.rollout_policy.choose_action(game, rng),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    #[test]
+    fn ismcts_plays_legal_actions() {
+        let rng = &mut rand::rng();
+        let mut game =
+            GameState::new(2, "first", &RuleSet::default(), rng).unwrap();
+        let mut agent = IsmctsAgent::new(SearchBudget::iterations(30), 1);
+        for _ in 0..10 {
+            let action = agent.choose_action(&game, rng);
+            game.process_action(&action, rng).unwrap();
+        }
+    }
+}
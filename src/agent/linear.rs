@@ -0,0 +1,403 @@
+use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::agent::common::*;
+use crate::agent::turn_planner::{
+    GameScorer, find_best_action, traversability,
+};
+use crate::data::Node;
+use crate::game::{ActionOutcome, GameState, PlayerAction};
+use crate::rules::RuleSet;
+
+/// Number of features (and weights) in the linear value function, in order:
+/// - 0: bias
+/// - 1: hex distance to the finish
+/// - 2: distance weighted by hex cost
+/// - 3: distance weighted by how hard each hex is to enter with our cards
+/// - 4-6: jungle/desert/water movement in our deck (Player::sum_movement)
+/// - 7: number of cards
+/// - 8: number of curses
+/// - 9: number of bonus tokens
+/// - 10: number of broken barriers
+/// - 11: the leading opponent's lead in hex distance
+/// - 12: whether we've reached the finish
+///
+/// Distances are divided by the map's maximum distance, and counts by 10.
+pub const NUM_VALUE_FEATURES: usize = 13;
+
+type Features = [f64; NUM_VALUE_FEATURES];
+
+/// Hand-picked starting weights, roughly matching the turn planners.
+pub const DEFAULT_VALUE_WEIGHTS: [f64; NUM_VALUE_FEATURES] = [
+    0.5, -0.3, -0.1, -0.1, 0.02, 0.02, 0.02, 0.0, -0.05, 0.05, 0.05, -0.3, 0.5,
+];
+
+/// Plans turns like the other turn planners, but scores game states with a
+/// linear value function: an estimate of the chance to win.
+pub(super) struct LinearValueAgent {
+    pub weights: Vec<f64>,
+    // Limits on the search for each action.
+    pub budget: SearchBudget,
+    // Cost-weighted distance from each node to the finish.
+    cost_dists: Vec<f64>,
+    // Problems since the last take_errors call, e.g. unreadable weights.
+    errors: Vec<String>,
+}
+impl LinearValueAgent {
+    pub(super) fn new(weights: Vec<f64>, budget: SearchBudget) -> Self {
+        Self {
+            weights,
+            budget,
+            cost_dists: vec![],
+            errors: vec![],
+        }
+    }
+    pub(super) fn report(&mut self, err: String) {
+        self.errors.push(err);
+    }
+}
+impl Agent for LinearValueAgent {
+    fn on_game_start(&mut self, game: &GameState, _player_idx: usize) {
+        self.cost_dists = cost_dists(game);
+    }
+    fn choose_action(
+        &mut self,
+        game: &GameState,
+        rng: &mut dyn RngCore,
+    ) -> PlayerAction {
+        if self.cost_dists.is_empty() {
+            self.cost_dists = cost_dists(game);
+        }
+        find_best_action(self, game, rng, &self.budget).action
+    }
+    fn take_errors(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.errors)
+    }
+}
+impl GameScorer for LinearValueAgent {
+    fn score_game_state(&self, game: &GameState) -> f64 {
+        let x = value_features(game, game.curr_player_idx, &self.cost_dists);
+        dot(&self.weights, &x)
+    }
+    // The search stops at draws, so value the state before drawing.
+    fn draw_cutoff_score(&self, game: &GameState) -> f64 {
+        self.score_game_state(game)
+    }
+    // The same outcomes the value function is trained towards.
+    fn game_over_score(&self, game: &GameState, player_idx: usize) -> f64 {
+        outcome(&game.player_scores(), player_idx)
+    }
+}
+
+// 1 for a win, 0 for a loss, and 0.5 for a draw.
+fn outcome(scores: &[i32], player_idx: usize) -> f64 {
+    let mine = scores[player_idx];
+    let best_other = (0..scores.len())
+        .filter(|&i| i != player_idx)
+        .map(|i| scores[i])
+        .max()
+        .unwrap_or(mine);
+    match mine.cmp(&best_other) {
+        std::cmp::Ordering::Greater => 1.0,
+        std::cmp::Ordering::Less => 0.0,
+        std::cmp::Ordering::Equal => 0.5,
+    }
+}
+
+fn cost_dists(game: &GameState) -> Vec<f64> {
+    game.graph
+        .distances_to_finish(&game.map, 0, |node: &Node| node.cost as f64)
+}
+
+fn dot(weights: &[f64], x: &[f64]) -> f64 {
+    weights.iter().zip(x).map(|(w, x)| w * x).sum()
+}
+
+/// Features of `game` from `player_idx`'s point of view (see
+/// NUM_VALUE_FEATURES). `cost_dists` are cost-weighted distances from each
+/// node to the finish.
+fn value_features(
+    game: &GameState,
+    player_idx: usize,
+    cost_dists: &[f64],
+) -> Features {
+    let me = &game.players[player_idx];
+    let max_dist = game.graph.max_dist.max(1) as f64;
+    let hex_dist = |pos| {
+        let idx = game.map.node_idx(pos).unwrap();
+        (game.graph.dists[idx] as f64).min(max_dist)
+    };
+    let my_idx = game.map.node_idx(me.position).unwrap();
+    let my_board_idx = game.map.node_at_idx(my_idx).unwrap().board_idx;
+    let my_cards = me.all_cards();
+    let dynamic_dists = game.graph.distances_to_finish(
+        &game.map,
+        my_board_idx,
+        |node: &Node| 1.0 - traversability(node, &my_cards).ln(),
+    );
+    let my_dist = hex_dist(me.position);
+    let best_other_dist = game
+        .players
+        .iter()
+        .enumerate()
+        .filter(|&(i, p)| i != player_idx && !p.resigned)
+        .map(|(_, p)| hex_dist(p.position))
+        .min_by(f64::total_cmp)
+        .unwrap_or(my_dist);
+    let movement = me.sum_movement();
+    let num_curses: usize = my_cards
+        .iter()
+        .filter(|(c, _)| c.is_curse())
+        .map(|&(_, n)| n)
+        .sum();
+    // Unreachable hexes have infinite distances.
+    let clamp = |d: f64| d.min(4.0 * max_dist) / max_dist;
+    [
+        1.0,
+        my_dist / max_dist,
+        clamp(cost_dists.get(my_idx).copied().unwrap_or(f64::INFINITY)),
+        clamp(dynamic_dists[my_idx]),
+        movement[0] as f64 / 10.0,
+        movement[1] as f64 / 10.0,
+        movement[2] as f64 / 10.0,
+        me.num_cards() as f64 / 10.0,
+        num_curses as f64 / 10.0,
+        me.tokens.len() as f64 / 10.0,
+        me.broken_barriers.len() as f64 / 10.0,
+        (my_dist - best_other_dist) / max_dist,
+        (my_dist == 0.0) as u8 as f64,
+    ]
+}
+
+/// Settings for learning linear value weights by TD(λ) self-play.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TdConfig {
+    pub games: usize,
+    // Learning rate.
+    pub alpha: f64,
+    // Trace decay: 0 learns only from the next state, 1 from the outcome.
+    pub lambda: f64,
+    // Chance of a random legal action instead of the agent's choice.
+    pub epsilon: f64,
+    // Search budget for the agents' own moves.
+    pub budget: SearchBudget,
+    // Named layouts, used in turn.
+    pub layouts: Vec<String>,
+    pub rules: RuleSet,
+    // Games still running after this many actions are scored as draws.
+    pub max_actions: usize,
+    pub seed: Option<u64>,
+}
+
+impl Default for TdConfig {
+    fn default() -> Self {
+        Self {
+            games: 100,
+            alpha: 0.01,
+            lambda: 0.7,
+            epsilon: 0.05,
+            budget: SearchBudget::iterations(500),
+            layouts: vec!["easy1".into()],
+            rules: RuleSet::default(),
+            max_actions: 2000,
+            seed: None,
+        }
+    }
+}
+
+/// Learn value weights by two-player self-play with TD(λ), starting from
+/// `weights`. After each game, `on_game` is called with the game number and
+/// the current weights, e.g. to save progress.
+pub fn train_td(
+    config: &TdConfig,
+    weights: &mut [f64],
+    mut on_game: impl FnMut(usize, &[f64]),
+) -> Result<(), String> {
+    if config.layouts.is_empty() {
+        return Err("Training needs at least one layout".into());
+    }
+    if weights.len() != NUM_VALUE_FEATURES {
+        return Err(format!(
+            "Expected {NUM_VALUE_FEATURES} weights, got {}",
+            weights.len()
+        ));
+    }
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_rng(&mut rand::rng()),
+    };
+    for game_idx in 0..config.games {
+        let layout = &config.layouts[game_idx % config.layouts.len()];
+        let (states, outcomes) =
+            self_play(config, layout, weights.to_vec(), &mut rng)?;
+        for (player_states, outcome) in states.iter().zip(outcomes) {
+            td_update(config, weights, player_states, outcome);
+        }
+        on_game(game_idx, weights);
+    }
+    Ok(())
+}
+
+// Play one game, returning each player's features after each of their
+// actions, and each player's outcome (1 win, 0 loss, 0.5 draw).
+fn self_play(
+    config: &TdConfig,
+    layout: &str,
+    weights: Vec<f64>,
+    rng: &mut StdRng,
+) -> Result<(Vec<Vec<Features>>, Vec<f64>), String> {
+    let mut game = GameState::new(2, layout, &config.rules, rng)
+        .map_err(|e| e.to_string())?;
+    let mut agents: Vec<_> = (0..2)
+        .map(|_| LinearValueAgent::new(weights.clone(), config.budget))
+        .collect();
+    for (i, agent) in agents.iter_mut().enumerate() {
+        agent.on_game_start(&game, i);
+    }
+    let cost_dists = cost_dists(&game);
+    let mut states = vec![vec![]; 2];
+    let mut finished = false;
+    for _ in 0..config.max_actions {
+        let seat = game.curr_player_idx;
+        let action = if rng.random_bool(config.epsilon) {
+            legal_actions(&game).choose(rng).unwrap().clone()
+        } else {
+            agents[seat].choose_action(&game, rng)
+        };
+        let outcome = game.process_action(&action, rng)?;
+        states[seat].push(value_features(&game, seat, &cost_dists));
+        if matches!(outcome, ActionOutcome::GameOver) {
+            finished = true;
+            break;
+        }
+    }
+    let scores = game.player_scores();
+    let outcomes = (0..2)
+        .map(|i| if finished { outcome(&scores, i) } else { 0.5 })
+        .collect();
+    Ok((states, outcomes))
+}
+
+// Backward-view TD(λ) over one player's states, ending with the outcome.
+fn td_update(
+    config: &TdConfig,
+    weights: &mut [f64],
+    states: &[Features],
+    outcome: f64,
+) {
+    let mut trace = [0.0; NUM_VALUE_FEATURES];
+    for (t, x) in states.iter().enumerate() {
+        let value = dot(weights, x);
+        let next_value = match states.get(t + 1) {
+            Some(next) => dot(weights, next),
+            None => outcome,
+        };
+        let delta = next_value - value;
+        for (e, xi) in trace.iter_mut().zip(x) {
+            *e = config.lambda * *e + xi;
+        }
+        for (w, e) in weights.iter_mut().zip(&trace) {
+            *w += config.alpha * delta * e;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn td_learns_outcomes() {
+        // A single state that always leads to a win converges to 1.
+        let config = TdConfig::default();
+        let mut weights = vec![0.0; NUM_VALUE_FEATURES];
+        let mut x = [0.0; NUM_VALUE_FEATURES];
+        x[0] = 1.0;
+        for _ in 0..1000 {
+            td_update(&config, &mut weights, &[x], 1.0);
+        }
+        assert!((weights[0] - 1.0).abs() < 1e-3);
+
+        // Self-play runs and keeps the weights finite.
+        let config = TdConfig {
+            games: 1,
+            budget: SearchBudget::iterations(20),
+            layouts: vec!["first".into()],
+            max_actions: 200,
+            seed: Some(1),
+            ..Default::default()
+        };
+        let mut weights = DEFAULT_VALUE_WEIGHTS.to_vec();
+        let mut num_games = 0;
+        train_td(&config, &mut weights, |_, _| num_games += 1).unwrap();
+        assert_eq!(num_games, 1);
+        assert!(weights.iter().all(|w| w.is_finite()));
+        // The weights must match the features.
+        let mut short = vec![0.0; NUM_VALUE_FEATURES - 1];
+        assert!(train_td(&config, &mut short, |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn search_uses_value_scale() {
+        let rng = &mut rand::rng();
+        let game =
+            GameState::new(2, "first", &RuleSet::default(), rng).unwrap();
+        let mut agent = LinearValueAgent::new(
+            DEFAULT_VALUE_WEIGHTS.to_vec(),
+            SearchBudget::iterations(20),
+        );
+        agent.on_game_start(&game, 0);
+        // Stopping at a draw keeps the current estimate, not a loss.
+        let value = agent.score_game_state(&game);
+        assert_eq!(agent.draw_cutoff_score(&game), value);
+        assert_eq!(outcome(&[3, 1], 0), 1.0);
+        assert_eq!(outcome(&[3, 1], 1), 0.0);
+        assert_eq!(outcome(&[2, 2], 1), 0.5);
+    }
+
+    #[test]
+    fn weights_files() {
+        let path = std::env::temp_dir()
+            .join(format!("durango-linear-{}.json", std::process::id()));
+        let path_str = path.to_str().unwrap().to_string();
+        let spec = |file: &str| {
+            serde_json::from_str::<crate::agent::AgentSpec>(&format!(
+                r#"{{"kind": "linear", "weights_file": {file:?}}}"#
+            ))
+            .unwrap()
+        };
+        // A spec as saved by train_td, or a plain array.
+        let weights = vec![0.25; NUM_VALUE_FEATURES];
+        let saved = crate::agent::AgentSpec::Linear {
+            weights: weights.clone(),
+            weights_file: None,
+            budget: SearchBudget::iterations(20),
+        };
+        for json in [
+            serde_json::to_string(&saved).unwrap(),
+            serde_json::to_string(&weights).unwrap(),
+        ] {
+            std::fs::write(&path, json).unwrap();
+            let loaded = crate::agent::load_value_weights(&path_str).unwrap();
+            assert_eq!(loaded, weights);
+            let mut agent = spec(&path_str).create();
+            assert!(agent.take_errors().is_empty());
+        }
+        std::fs::write(&path, "[1.0]").unwrap();
+        assert!(crate::agent::load_value_weights(&path_str).is_err());
+        std::fs::remove_file(&path).unwrap();
+        // Missing files are reported, and the agent still plays.
+        let mut agent = spec(&path_str).create();
+        assert_eq!(agent.take_errors().len(), 1);
+        // So are specs with the wrong number of weights.
+        let wrong = crate::agent::AgentSpec::Linear {
+            weights: vec![0.25; NUM_VALUE_FEATURES + 1],
+            weights_file: None,
+            budget: SearchBudget::iterations(20),
+        };
+        assert_eq!(wrong.create().take_errors().len(), 1);
+    }
+}
@@ -6,7 +6,7 @@ use rand::{Rng, RngCore};
 pub(super) struct RandomAgent {}
 impl Agent for RandomAgent {
     fn choose_action(
-        &self,
+        &mut self,
         game: &GameState,
         rng: &mut dyn RngCore,
     ) -> PlayerAction {
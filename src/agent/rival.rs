@@ -0,0 +1,232 @@
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::common::*;
+use crate::agent::turn_planner::{
+    EvalWeights, GameScorer, find_best_action, traversability,
+};
+use crate::data::{BonusToken, Node};
+use crate::game::{GameState, PlayerAction};
+use crate::player::Player;
+
+/// How much the opponent-aware planner cares about its opponents.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RivalWeights {
+    // How much the leading opponent's progress counts against our own.
+    pub opponent: f64,
+    // Value of each hex we add to an opponent's path by standing in it,
+    // as a fraction of a hex of our own progress.
+    pub block: f64,
+    // Blocks only last a turn or so, so they're worth at most this many hexes.
+    pub max_block: f64,
+    // Extra value, as a fraction of the card's value, for holding cards
+    // whose supply is down to one copy or has run out: we beat the opponents
+    // to the last copies.
+    pub denial: f64,
+    // Once the leader is estimated to be this many turns from the finish,
+    // stop valuing deck building and just race.
+    pub rush_turns: f64,
+}
+
+impl Default for RivalWeights {
+    fn default() -> Self {
+        Self {
+            opponent: 0.5,
+            block: 0.5,
+            max_block: 3.0,
+            denial: 0.5,
+            rush_turns: 1.0,
+        }
+    }
+}
+
+/// Plans like the dynamic planner, but also scores the opponents: their
+/// progress, how much we're in their way, the shop cards we're racing for,
+/// and whether the race is close enough that only speed matters.
+pub(super) struct RivalTurnPlanner {
+    // Limits on the search for each action.
+    pub budget: SearchBudget,
+    pub weights: EvalWeights,
+    pub rival: RivalWeights,
+}
+impl Agent for RivalTurnPlanner {
+    fn choose_action(
+        &mut self,
+        game: &GameState,
+        rng: &mut dyn RngCore,
+    ) -> PlayerAction {
+        find_best_action(self, game, rng, &self.budget).action
+    }
+}
+
+// How far a player is from the finish.
+struct Race {
+    // Dynamic-cost distance, ignoring other players.
+    dist: f64,
+    // Extra distance when other players' hexes can't be entered.
+    blocked_by: f64,
+    // Rough number of turns to finish.
+    turns: f64,
+}
+
+impl RivalTurnPlanner {
+    fn race(&self, game: &GameState, player_idx: usize) -> Race {
+        let player = &game.players[player_idx];
+        let idx = game.map.node_idx(player.position).unwrap();
+        let board_idx = game.map.node_at_idx(idx).unwrap().board_idx;
+        let cards = player.all_cards();
+        let cost_fn = |node: &Node| 1.0 - traversability(node, &cards).ln();
+        let dist = game
+            .graph
+            .distances_to_finish(&game.map, board_idx, cost_fn)[idx];
+        let can_share = player
+            .tokens
+            .iter()
+            .any(|t| matches!(t, BonusToken::ShareHex));
+        let occupied: Vec<usize> = game
+            .players
+            .iter()
+            .enumerate()
+            .filter(|&(i, p)| i != player_idx && !p.resigned)
+            .filter_map(|(_, p)| game.map.node_idx(p.position))
+            .collect();
+        let blocked_by = if can_share || occupied.is_empty() {
+            0.0
+        } else {
+            let blocked = game
+                .graph
+                .distances_avoiding(&game.map, board_idx, &occupied, cost_fn)
+                [idx];
+            // Being walled off entirely (infinite distance) is capped too.
+            (blocked - dist).min(self.rival.max_block)
+        };
+        Race {
+            dist,
+            blocked_by,
+            turns: dist / reach(player, game.rules.hand_size),
+        }
+    }
+
+    // Bonus for holding market cards that are scarce: sold out, or down to
+    // their last copy. Bought-out cards leave the market entirely, so any card
+    // we hold that isn't there (and isn't a starting card or a curse, which
+    // never came from it) is sold out.
+    fn denial_value(&self, game: &GameState, me: &Player) -> f64 {
+        let mut value = 0.0;
+        for (card, count) in me.all_cards() {
+            if card.is_curse()
+                || game.rules.starting_deck.iter().any(|c| c.name == card.name)
+            {
+                continue;
+            }
+            let remaining = game
+                .shop
+                .iter()
+                .chain(&game.storage)
+                .find(|c| c.card.name == card.name)
+                .map_or(0, |c| c.quantity);
+            if remaining <= 1 {
+                value += self.weights.score_card(card) * count as f64;
+            }
+        }
+        value * self.rival.denial
+    }
+}
+
+// Rough number of hexes a player can cover in a turn: the hand size times
+// the average movement per card.
+fn reach(player: &Player, hand_size: usize) -> f64 {
+    let movement = player.sum_movement();
+    let per_card = movement.iter().map(|&m| m as f64).sum::<f64>()
+        / player.num_cards().max(1) as f64;
+    (per_card * hand_size as f64).max(1.0)
+}
+
+impl GameScorer for RivalTurnPlanner {
+    fn score_game_state(&self, game: &GameState) -> f64 {
+        let me_idx = game.curr_player_idx;
+        let me = game.curr_player();
+        let mine = self.race(game, me_idx);
+        let mut score =
+            self.weights.score_progress(me, mine.dist + mine.blocked_by);
+
+        let opponents: Vec<(usize, Race)> = (0..game.players.len())
+            .filter(|&i| i != me_idx && !game.players[i].resigned)
+            .map(|i| (i, self.race(game, i)))
+            .collect();
+        let leader = opponents.iter().max_by(|(a, ra), (b, rb)| {
+            let pa = self.weights.score_progress(&game.players[*a], ra.dist);
+            let pb = self.weights.score_progress(&game.players[*b], rb.dist);
+            pa.total_cmp(&pb)
+        });
+        if let Some((i, race)) = leader {
+            let progress =
+                self.weights.score_progress(&game.players[*i], race.dist);
+            score -= self.rival.opponent * progress;
+        }
+        // Standing in an opponent's way is worth some of our own progress.
+        let blocks: f64 = opponents.iter().map(|(_, r)| r.blocked_by).sum();
+        score -= self.rival.block * blocks * self.weights.distance;
+
+        // Deck building only pays off if there's time to use the cards.
+        let leader_turns = opponents
+            .iter()
+            .map(|(_, r)| r.turns)
+            .fold(f64::INFINITY, f64::min);
+        if leader_turns > self.rival.rush_turns {
+            score += self.weights.score_player_cards(me);
+            score += self.denial_value(game, me);
+        }
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    #[test]
+    fn rival_plays() {
+        let rng = &mut rand::rng();
+        let mut game =
+            GameState::new(2, "first", &RuleSet::default(), rng).unwrap();
+        let mut agent = RivalTurnPlanner {
+            budget: SearchBudget::iterations(20),
+            weights: EvalWeights::default(),
+            rival: RivalWeights::default(),
+        };
+        for _ in 0..20 {
+            let action = agent.choose_action(&game, rng);
+            game.process_action(&action, rng).unwrap();
+        }
+    }
+
+    #[test]
+    fn rival_values_sold_out_cards() {
+        let rng = &mut rand::rng();
+        let mut game =
+            GameState::new(2, "first", &RuleSet::default(), rng).unwrap();
+        let agent = RivalTurnPlanner {
+            budget: SearchBudget::iterations(20),
+            weights: EvalWeights::default(),
+            rival: RivalWeights::default(),
+        };
+        // Hold a copy of a shop card while others are still for sale.
+        let idx = game
+            .shop
+            .iter()
+            .position(|c| {
+                c.quantity > 1 && agent.weights.score_card(&c.card) > 0.0
+            })
+            .unwrap();
+        let card = game.shop[idx].card.clone();
+        game.players[0].deck.push(card);
+        let before = agent.score_game_state(&game);
+        // Buying out the rest removes the card from the market.
+        game.shop.remove(idx);
+        let after = agent.score_game_state(&game);
+        assert!(after > before, "{after} <= {before}");
+    }
+}
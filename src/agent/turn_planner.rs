@@ -1,5 +1,6 @@
 use rand::RngCore;
-use std::cell::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use crate::agent::common::*;
 use crate::cards::{Card, CardAction};
@@ -7,93 +8,193 @@ use crate::data::{BonusToken, Node, Terrain};
 use crate::game::{ActionOutcome, DrawAction, GameState, PlayerAction};
 use crate::player::Player;
 
-trait GameScorer {
+/// Weights for the turn planners' evaluation of a game state. The defaults
+/// are hand-tuned; see `tuning` for finding better ones by self-play.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EvalWeights {
+    // Per bonus token held.
+    pub token: f64,
+    // Per barrier broken.
+    pub barrier: f64,
+    // Per unit of distance to the finish (negative: closer is better).
+    pub distance: f64,
+    // Card values, used by the static planner only. Plain movement cards
+    // are worth `movement` per movement point.
+    pub movement: f64,
+    pub free_move: f64,
+    // Per card drawn.
+    pub draw: f64,
+    // Per card drawn (and optionally trashed).
+    pub draw_and_trash: f64,
+    pub free_buy: f64,
+    // Per card discarded by each opponent.
+    pub sabotage: f64,
+    pub gain_token: f64,
+    pub hex: f64,
+    pub curse: f64,
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        Self {
+            token: 10.0,
+            barrier: 100.0,
+            distance: -1000.0,
+            movement: 1.0,
+            free_move: 5.0,
+            draw: 2.0,
+            draw_and_trash: 3.0,
+            free_buy: 4.0,
+            sabotage: 3.0,
+            gain_token: 2.0,
+            hex: 3.0,
+            curse: -5.0,
+        }
+    }
+}
+
+impl EvalWeights {
+    /// All weights by name, for tuning.
+    pub fn params_mut(&mut self) -> [(&'static str, &mut f64); 12] {
+        [
+            ("token", &mut self.token),
+            ("barrier", &mut self.barrier),
+            ("distance", &mut self.distance),
+            ("movement", &mut self.movement),
+            ("free_move", &mut self.free_move),
+            ("draw", &mut self.draw),
+            ("draw_and_trash", &mut self.draw_and_trash),
+            ("free_buy", &mut self.free_buy),
+            ("sabotage", &mut self.sabotage),
+            ("gain_token", &mut self.gain_token),
+            ("hex", &mut self.hex),
+            ("curse", &mut self.curse),
+        ]
+    }
+
+    // Score for everything but the cards.
+    pub(super) fn score_progress(
+        &self,
+        player: &Player,
+        dist_to_finish: f64,
+    ) -> f64 {
+        (player.tokens.len() as f64) * self.token
+            + (player.broken_barriers.len() as f64) * self.barrier
+            + dist_to_finish * self.distance
+    }
+
+    pub(super) fn score_player_cards(&self, player: &Player) -> f64 {
+        let mut score = 0.0;
+        for (card, count) in player.all_cards() {
+            score += self.score_card(card) * (count as f64);
+        }
+        score
+    }
+
+    pub(super) fn score_card(&self, card: &Card) -> f64 {
+        match card.action {
+            None => card.movement.iter().sum::<u8>() as f64 * self.movement,
+            Some(CardAction::FreeMove) => self.free_move,
+            Some(CardAction::Draw(n)) => self.draw * (n as f64),
+            Some(CardAction::DrawAndTrash(n)) => {
+                self.draw_and_trash * (n as f64)
+            }
+            Some(CardAction::FreeBuy) => self.free_buy,
+            Some(CardAction::Sabotage(n)) => self.sabotage * (n as f64),
+            Some(CardAction::GainToken(_)) => self.gain_token,
+            Some(CardAction::Hex) => self.hex,
+            Some(CardAction::Curse) => self.curse,
+        }
+    }
+}
+
+pub(super) trait GameScorer {
     fn score_game_state(&self, game: &GameState) -> f64;
+    /// Score for a line that reaches a draw action, which the search doesn't
+    /// look past. The default ranks drawing above any scored state on the
+    /// distance-based scale.
+    fn draw_cutoff_score(&self, _game: &GameState) -> f64 {
+        0.0
+    }
+    /// Score for a line where `player_idx`'s action ended the game. The
+    /// default ends the search, since nothing can beat it.
+    fn game_over_score(&self, _game: &GameState, _player_idx: usize) -> f64 {
+        f64::MAX
+    }
 }
 
 pub(super) struct StaticDistanceTurnPlanner {
     // Score single-hex distances as node.cost^cost_exponent.
     pub cost_exponent: i32,
-    dists: OnceCell<Vec<f64>>,
+    // Limits on the search for each action.
+    pub budget: SearchBudget,
+    pub weights: EvalWeights,
+    // Distance from each node to the finish, computed once per game.
+    dists: Vec<f64>,
 }
 impl StaticDistanceTurnPlanner {
-    pub(super) fn new(cost_exponent: i32) -> Self {
+    pub(super) fn new(
+        cost_exponent: i32,
+        budget: SearchBudget,
+        weights: EvalWeights,
+    ) -> Self {
         Self {
             cost_exponent,
-            dists: OnceCell::new(),
+            budget,
+            weights,
+            dists: vec![],
         }
     }
-    fn get_dists(&self, game: &GameState) -> &[f64] {
-        self.dists.get_or_init(|| {
-            if self.cost_exponent == 0 {
-                return game.graph.dists.iter().map(|&d| d as f64).collect();
-            }
+    fn compute_dists(&mut self, game: &GameState) {
+        self.dists = if self.cost_exponent == 0 {
+            game.graph.dists.iter().map(|&d| d as f64).collect()
+        } else {
             game.graph.distances_to_finish(&game.map, 0, |node: &Node| {
                 (node.cost as f64).powi(self.cost_exponent)
             })
-        })
+        };
     }
 }
 impl Agent for StaticDistanceTurnPlanner {
+    fn on_game_start(&mut self, game: &GameState, _player_idx: usize) {
+        self.compute_dists(game);
+    }
     fn choose_action(
-        &self,
+        &mut self,
         game: &GameState,
         rng: &mut dyn RngCore,
     ) -> PlayerAction {
-        let (best, num_sims) = find_best_action(self, game, rng, 0);
-        if num_sims >= 10000 {
-            println!("Sims = {num_sims}\t Score = {}", best.score);
+        // In case we joined without being told the game started.
+        if self.dists.is_empty() {
+            self.compute_dists(game);
         }
-        best.action
+        find_best_action(self, game, rng, &self.budget).action
     }
 }
 impl GameScorer for StaticDistanceTurnPlanner {
     fn score_game_state(&self, game: &GameState) -> f64 {
         let me = game.curr_player();
         let my_idx = game.map.node_idx(me.position).unwrap();
-        let dist_to_finish = self.get_dists(game)[my_idx];
-        let num_tokens = me.tokens.len();
-        let num_barriers = me.broken_barriers.len();
-        let card_value = score_player_cards(me);
+        let dist_to_finish = self.dists[my_idx];
         // TODO: better scoring function
-        card_value
-            + (num_tokens as f64) * 10.0
-            + (num_barriers as f64) * 100.0
-            + dist_to_finish * -1000.0
-    }
-}
-
-fn score_player_cards(player: &Player) -> f64 {
-    let mut score = 0.0;
-    for (card, count) in player.all_cards() {
-        score += score_card(card) * (count as f64);
+        self.weights.score_player_cards(me)
+            + self.weights.score_progress(me, dist_to_finish)
     }
-    score
 }
 
-fn score_card(card: &Card) -> f64 {
-    match card.action {
-        None => card.movement.iter().sum::<u8>() as f64,
-        Some(CardAction::FreeMove) => 5.0,
-        Some(CardAction::Draw(n)) => 2.0 * (n as f64),
-        Some(CardAction::DrawAndTrash(n)) => 3.0 * (n as f64),
-        Some(CardAction::FreeBuy) => 4.0,
-    }
+pub(super) struct DynamicCostTurnPlanner {
+    // Limits on the search for each action.
+    pub budget: SearchBudget,
+    pub weights: EvalWeights,
 }
-
-#[derive(Default)]
-pub(super) struct DynamicCostTurnPlanner {}
 impl Agent for DynamicCostTurnPlanner {
     fn choose_action(
-        &self,
+        &mut self,
         game: &GameState,
         rng: &mut dyn RngCore,
     ) -> PlayerAction {
-        let (best, num_sims) = find_best_action(self, game, rng, 0);
-        if num_sims >= 10000 {
-            println!("Sims = {num_sims}\t Score = {}", best.score);
-        }
-        best.action
+        find_best_action(self, game, rng, &self.budget).action
     }
 }
 impl GameScorer for DynamicCostTurnPlanner {
@@ -107,18 +208,17 @@ impl GameScorer for DynamicCostTurnPlanner {
             my_board_idx,
             |node: &Node| 1.0 - traversability(node, &my_cards).ln(),
         );
-        let num_tokens = me.tokens.len();
-        let num_barriers = me.broken_barriers.len();
         // No need to score cards here since traversability already factors them in.
-        (num_tokens as f64) * 10.0
-            + (num_barriers as f64) * 100.0
-            + dists[my_idx] * -1000.0
+        self.weights.score_progress(me, dists[my_idx])
     }
 }
 
 // Compute the likelihood of being able to traverse this node,
 // given the player's cards.
-fn traversability(node: &Node, player_cards: &[(&Card, usize)]) -> f64 {
+pub(super) fn traversability(
+    node: &Node,
+    player_cards: &[(&Card, usize)],
+) -> f64 {
     let mut num_can_traverse = 0;
     let mut total_cards = 0;
     for &(card, count) in player_cards {
@@ -134,6 +234,10 @@ fn traversability(node: &Node, player_cards: &[(&Card, usize)]) -> f64 {
                 num_can_traverse += count;
                 total_cards += count;
             }
+            Some(CardAction::Curse) => {
+                // Curses never help, and they take up space in the hand.
+                total_cards += count;
+            }
             Some(_) => {
                 // Other actions don't help with movement, but we can make them
                 // valuable to the agent by excluding them from the total.
@@ -153,76 +257,98 @@ fn can_traverse(node: &Node, card: &Card) -> bool {
     }
 }
 
-struct ActionScore {
-    action: PlayerAction,
-    score: f64,
+pub(super) struct ActionScore {
+    pub action: PlayerAction,
+    pub score: f64,
 }
 const MAX_DEPTH: usize = 5;
-fn find_best_action(
+
+// Search with iterative deepening, up to MAX_DEPTH actions ahead or until the
+// budget runs out. Returns the best action from the deepest search that
+// scored at least one action.
+pub(super) fn find_best_action(
     agent: &impl GameScorer,
     game: &GameState,
     rng: &mut dyn RngCore,
-    depth: usize,
-) -> (ActionScore, usize) {
+    budget: &SearchBudget,
+) -> ActionScore {
     // Hack: to avoid the possibility of an infinite loop of drawing cards,
     // only consider buy/move/trash actions if no draw actions are possible.
     // This also means the agent can't cheat by looking ahead in the deck.
     if let Some(draw) = valid_draw_actions(game).into_iter().next() {
         let action = PlayerAction::Draw(draw);
-        return (ActionScore { action, score: 0.0 }, 0);
+        let score = agent.draw_cutoff_score(game);
+        return ActionScore { action, score };
     }
 
     let num_cards = game.curr_player().hand.len();
-    let mut best = ActionScore {
-        action: if num_cards == 0 {
+    let stop_action = || {
+        if num_cards == 0 {
             PlayerAction::FinishTurn
         } else {
             PlayerAction::Discard((0..num_cards).collect())
-        },
-        score: agent.score_game_state(game),
+        }
+    };
+    let stop_score = agent.score_game_state(game);
+    let mut best = ActionScore {
+        action: stop_action(),
+        score: stop_score,
     };
-    if depth >= MAX_DEPTH {
-        return (best, 0);
-    }
 
-    let mut num_sims = 0;
-    for action in all_actions(game) {
-        let mut simulated_game = game.clone();
-        let outcome = match simulated_game.process_action(&action, rng) {
-            Ok(outcome) => outcome,
-            Err(e) => {
-                let p = simulated_game.curr_player();
-                panic!(
-                    "Simulation failed: {e}\n for move: {action:?}\n hand={:?}\n tokens={:?}",
-                    p.hand, p.tokens
-                );
-            }
-        };
-        num_sims += 1;
-        // If this ends the game, no need to keep going.
-        if matches!(outcome, ActionOutcome::GameOver) {
-            return (
-                ActionScore {
-                    action,
-                    score: f64::MAX,
-                },
-                num_sims,
-            );
-        }
-        // Otherwise, recurse.
-        let (res, ct) =
-            find_best_action(agent, &simulated_game, rng, depth + 1);
-        num_sims += ct;
-        if res.score > best.score {
-            best = ActionScore {
-                action,
-                score: res.score,
+    let mut search = Search {
+        agent,
+        tracker: budget.start(),
+        max_depth: 0,
+        num_sims: 0,
+        cache: HashMap::new(),
+    };
+    let mut root_actions = all_actions(game);
+    let mut sim_game = game.clone();
+    for max_depth in 1..=MAX_DEPTH {
+        search.max_depth = max_depth;
+        let mut scores = Vec::with_capacity(root_actions.len());
+        for action in &root_actions {
+            let Some(score) =
+                search.score_action(&mut sim_game, action, rng, 0)
+            else {
+                break;
             };
-            if best.score == f64::MAX {
+            scores.push(score);
+            if score == f64::MAX {
                 break;
             }
         }
+        if scores.is_empty() && !root_actions.is_empty() {
+            break; // out of budget before learning anything new
+        }
+        let mut depth_best = ActionScore {
+            action: stop_action(),
+            score: stop_score,
+        };
+        for (action, &score) in root_actions.iter().zip(&scores) {
+            if score > depth_best.score {
+                depth_best = ActionScore {
+                    action: action.clone(),
+                    score,
+                };
+            }
+        }
+        best = depth_best;
+        if scores.len() < root_actions.len() {
+            break; // out of budget, or found a winning action
+        }
+        // Move ordering: search the most promising actions first next time,
+        // so they're covered even if the budget runs out.
+        let mut order: Vec<usize> = (0..root_actions.len()).collect();
+        order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+        let mut old_actions: Vec<Option<PlayerAction>> =
+            root_actions.into_iter().map(Some).collect();
+        root_actions = order
+            .into_iter()
+            .map(|i| old_actions[i].take().unwrap())
+            .collect();
     }
+
     // Special case: if we'd simply discard 2+ cards, try using ReplaceHand.
     // We don't simulate this because it would "cheat" by looking at the deck.
     if num_cards > 1
@@ -238,7 +364,85 @@ fn find_best_action(
             token: Some(idx),
         });
     }
-    (best, num_sims)
+    best
+}
+
+// State shared across one iterative-deepening search.
+struct Search<'a, S> {
+    agent: &'a S,
+    tracker: BudgetTracker,
+    max_depth: usize,
+    num_sims: usize,
+    // Scores by (state hash, remaining depth), so transpositions are only
+    // searched once.
+    cache: HashMap<(u64, usize), f64>,
+}
+impl<S: GameScorer> Search<'_, S> {
+    // Score the best line reachable from `game` within `max_depth` actions.
+    // Returns None if the budget ran out.
+    fn search(
+        &mut self,
+        game: &mut GameState,
+        rng: &mut dyn RngCore,
+        depth: usize,
+    ) -> Option<f64> {
+        // Don't look past draw actions (see find_best_action).
+        if !valid_draw_actions(game).is_empty() {
+            return Some(self.agent.draw_cutoff_score(game));
+        }
+        let key = (game.hash(), self.max_depth - depth);
+        if let Some(&score) = self.cache.get(&key) {
+            return Some(score);
+        }
+        let mut best = self.agent.score_game_state(game);
+        if depth < self.max_depth {
+            for action in all_actions(game) {
+                let score = self.score_action(game, &action, rng, depth)?;
+                if score > best {
+                    best = score;
+                    if best == f64::MAX {
+                        break;
+                    }
+                }
+            }
+        }
+        self.cache.insert(key, best);
+        Some(best)
+    }
+
+    // Simulate `action`, then score the best line that follows it.
+    // The game is restored afterwards.
+    fn score_action(
+        &mut self,
+        game: &mut GameState,
+        action: &PlayerAction,
+        rng: &mut dyn RngCore,
+        depth: usize,
+    ) -> Option<f64> {
+        if self.tracker.exhausted(self.num_sims) {
+            return None;
+        }
+        let player_idx = game.curr_player_idx;
+        let (outcome, undo) = match game.apply(action, rng) {
+            Ok(res) => res,
+            Err(e) => {
+                let p = game.curr_player();
+                panic!(
+                    "Simulation failed: {e}\n for move: {action:?}\n hand={:?}\n tokens={:?}",
+                    p.hand, p.tokens
+                );
+            }
+        };
+        self.num_sims += 1;
+        // If this ends the game, no need to keep going.
+        let score = if matches!(outcome, ActionOutcome::GameOver) {
+            Some(self.agent.game_over_score(game, player_idx))
+        } else {
+            self.search(game, rng, depth + 1)
+        };
+        game.undo(undo);
+        score
+    }
 }
 
 // Get all valid actions for the current player, excluding draw-card actions.
@@ -262,13 +466,34 @@ fn all_actions(game: &GameState) -> Vec<PlayerAction> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rules::RuleSet;
 
     #[test]
     fn test_choose_action() {
         let rng = &mut rand::rng();
-        let game = GameState::new(2, "first", rng).unwrap();
-        let agent = StaticDistanceTurnPlanner::new(0);
+        let game =
+            GameState::new(2, "first", &RuleSet::default(), rng).unwrap();
+        let mut agent = StaticDistanceTurnPlanner::new(
+            0,
+            SearchBudget::default(),
+            EvalWeights::default(),
+        );
         let action = agent.choose_action(&game, rng);
-        println!("Chosen action: {:?}", action);
+        println!("Chosen action: {action}");
+    }
+
+    #[test]
+    fn search_budget() {
+        let rng = &mut rand::rng();
+        let mut game =
+            GameState::new(2, "first", &RuleSet::default(), rng).unwrap();
+        let mut agent = DynamicCostTurnPlanner {
+            budget: SearchBudget::iterations(5),
+            weights: EvalWeights::default(),
+        };
+        for _ in 0..10 {
+            let action = agent.choose_action(&game, rng);
+            game.process_action(&action, rng).unwrap();
+        }
     }
 }
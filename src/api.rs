@@ -1,12 +1,20 @@
 use blau_api::{DynSafeGameAPI, GameAPI, PlayerInfo, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use crate::{
-    agent::{Agent, create_agent},
-    cards::{BuyableCard, Card},
-    data::{AxialCoord, Barrier, BonusToken, BrokenBarrier, HexMap},
-    game::{ActionOutcome, GameState, PlayerAction},
+    agent::{Agent, AgentSpec},
+    cards::{BuyableCard, Expansion},
+    catalog::{CatalogEntry, MarketSetup},
+    data::{AxialCoord, BonusToken, BrokenBarrier, HexMap},
+    game::{ActionOutcome, EndReason, GameState, PlayerAction},
     player::Player,
+    record::{GameRecord, RecordedAction},
+    render::{self, SvgOptions},
+    rules::RuleSet,
+    view::PlayerView,
 };
 
 /// Parameters for game initialization.
@@ -14,45 +22,29 @@ use crate::{
 struct GameParams {
     // Named layout to use, e.g. "easy1"
     named_layout: String,
-}
-
-/// A view of another player's public information.
-#[derive(Serialize)]
-pub struct PublicPlayerInfo<'a> {
-    player_idx: usize,
-    position: AxialCoord,
-    hand: &'a [Card],
-    played: &'a [Card],
-    deck_size: usize,
-    discard_size: usize,
-    tokens: &'a [BonusToken],
-    broken_barriers: &'a [BrokenBarrier],
-}
-
-/// A view of my player's visible information.
-#[derive(Serialize)]
-pub struct MyPlayer<'a> {
-    // Public info.
-    #[serde(flatten)]
-    info: PublicPlayerInfo<'a>,
-    // Private info for my eyes only.
-    trashes: usize,
-    can_buy: bool,
-}
-
-/// A view of the game state for a specific player.
-#[derive(Serialize)]
-pub struct PlayerView<'a> {
-    map: &'a HexMap,
-    barriers: &'a [Barrier],
-    my_player: MyPlayer<'a>,
-    other_players: Vec<PublicPlayerInfo<'a>>,
-    bonuses: Vec<(&'a AxialCoord, usize)>,
-    shop: &'a [BuyableCard],
-    storage: &'a [BuyableCard],
-    round_idx: usize,
-    curr_player_idx: usize,
-    winner: Option<usize>,
+    // Optional card packs to enable, e.g. ["HeroesAndHexes"]
+    #[serde(default)]
+    expansions: Vec<Expansion>,
+    // Optional custom card catalog, replacing the base game's cards.
+    #[serde(default)]
+    catalog: Option<Vec<CatalogEntry>>,
+    // How to choose the opening shop, e.g. "Random" or {"Custom": [...]}
+    #[serde(default)]
+    market: MarketSetup,
+    // Core rule constants, defaulting to the standard rules.
+    #[serde(default)]
+    rules: RuleSet,
+    // If true, AI actions are only taken via DurangoAPI::step_agent.
+    #[serde(default)]
+    step_agents: bool,
+    // Agent specs by player ID, overriding the player's level. Any player
+    // with a spec here is played by an AI. Search budgets are capped at
+    // agent::MAX_API_BUDGET, and external agents are rejected.
+    #[serde(default)]
+    agents: HashMap<String, AgentSpec>,
+    // Seed for the game's shuffles and setup, random if not given.
+    #[serde(default)]
+    seed: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -61,6 +53,8 @@ struct FinalPlayerInfo {
     card_counts: Vec<BuyableCard>,
     tokens: Vec<BonusToken>,
     broken_barriers: Vec<BrokenBarrier>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    resigned: bool,
 }
 
 /// Final data to store for viewing completed games.
@@ -73,6 +67,9 @@ struct FinalState {
     named_layout: String,
     // For each player: sequence of (round_idx, q, r)
     history: Vec<Vec<(usize, i32, i32)>>,
+    // Missing for games recorded before round limits existed.
+    #[serde(default)]
+    end_reason: Option<EndReason>,
 }
 
 pub struct DurangoAPI {
@@ -88,53 +85,19 @@ pub struct DurangoAPI {
     game_over: bool,
     // Named layout used to define the map
     named_layout: String,
+    // If true, AI actions wait for calls to step_agent
+    step_agents: bool,
+    // Seeded by the record, for shuffles during play
+    rng: StdRng,
+    // Replayable record of the game, or None if restored from a final state
+    record: Option<GameRecord>,
 }
 
 impl DurangoAPI {
     fn view(&self, player_idx: usize) -> Result<String> {
-        let game = &self.state;
-        let winner = if self.game_over {
-            game.player_scores()
-                .iter()
-                .enumerate()
-                .max_by_key(|&(_, score)| score)
-                .map(|(i, _)| i)
-        } else {
-            None
-        };
-        let mut other_players = game
-            .players
-            .iter()
-            .enumerate()
-            .map(|(idx, p)| PublicPlayerInfo {
-                player_idx: idx,
-                position: p.position,
-                hand: &p.hand,
-                played: &p.played,
-                deck_size: p.deck_size(),
-                discard_size: p.discard.len(),
-                tokens: &p.tokens,
-                broken_barriers: &p.broken_barriers,
-            })
-            .collect::<Vec<_>>();
-        let player = &game.players[player_idx];
-        let my_player = MyPlayer {
-            info: other_players.swap_remove(player_idx),
-            trashes: player.trashes,
-            can_buy: player.can_buy,
-        };
-        let view = PlayerView {
-            map: &game.map,
-            barriers: &game.barriers,
-            my_player,
-            other_players,
-            bonuses: game.bonus_counts(),
-            shop: &game.shop,
-            storage: &game.storage,
-            round_idx: game.round_idx,
-            curr_player_idx: game.curr_player_idx,
-            winner,
-        };
+        let ai_seats: Vec<bool> =
+            self.agents.iter().map(Option::is_some).collect();
+        let view = PlayerView::new(&self.state, player_idx, &ai_seats);
         Ok(serde_json::to_string(&view)?)
     }
     fn do_action<F: FnMut(&str, &str)>(
@@ -143,8 +106,18 @@ impl DurangoAPI {
         mut notice_cb: F,
     ) -> Result<()> {
         // Take the action.
+        let player_idx = self.state.curr_player_idx;
+        let round_idx = self.state.round_idx;
+        let outcome = self.state.process_action(action, &mut self.rng)?;
+        if let Some(record) = &mut self.record {
+            record.add(
+                round_idx,
+                player_idx,
+                RecordedAction::Action(action.clone()),
+            );
+        }
         let mut ignored_idx = None;
-        match self.state.process_action(action, &mut rand::rng())? {
+        match outcome {
             ActionOutcome::Ok => {}
             ActionOutcome::GameOver => {
                 self.game_over = true;
@@ -153,6 +126,12 @@ impl DurangoAPI {
                 ignored_idx = Some(idx);
             }
         }
+        for agent in self.agents.iter_mut().flatten() {
+            agent.on_action(&self.state, player_idx, action, &outcome);
+        }
+        if self.game_over {
+            self.notify_game_end();
+        }
         // If this was a move, update history.
         if let PlayerAction::Move(mv) = action {
             let my_history = &mut self.history[self.state.curr_player_idx];
@@ -175,6 +154,14 @@ impl DurangoAPI {
         }
         Ok(())
     }
+    fn notify_game_end(&mut self) {
+        if let Some(record) = &mut self.record {
+            record.set_result(&self.state);
+        }
+        for agent in self.agents.iter_mut().flatten() {
+            agent.on_game_end(&self.state);
+        }
+    }
     fn human_player_idxs(&self) -> impl Iterator<Item = usize> + '_ {
         self.agents.iter().enumerate().filter_map(|(idx, agent)| {
             if agent.is_none() { Some(idx) } else { None }
@@ -184,36 +171,164 @@ impl DurangoAPI {
         &mut self,
         mut notice_cb: F,
     ) -> Result<()> {
-        while !self.game_over
-            && let Some(ai) = &self.agents[self.state.curr_player_idx]
-        {
-            let action = ai.choose_action(&self.state, &mut rand::rng());
-            self.do_action(&action, &mut notice_cb)?;
+        if self.step_agents {
+            return Ok(());
         }
+        while self.step_agent(&mut notice_cb)? {}
         Ok(())
     }
+    /// The ID of the AI player whose action is pending, if any.
+    pub fn pending_agent(&self) -> Option<&str> {
+        if self.game_over {
+            return None;
+        }
+        let idx = self.state.curr_player_idx;
+        match self.agents.get(idx) {
+            Some(Some(_)) => Some(self.player_ids[idx].as_str()),
+            _ => None,
+        }
+    }
+    /// Problems the AI players worked around since the last call, e.g. a
+    /// failed bot replaced by its fallback, as (player ID, message) pairs.
+    pub fn take_agent_errors(&mut self) -> Vec<(String, String)> {
+        let mut errors = vec![];
+        for (id, agent) in self.player_ids.iter().zip(&mut self.agents) {
+            if let Some(agent) = agent {
+                errors.extend(
+                    agent.take_errors().into_iter().map(|e| (id.clone(), e)),
+                );
+            }
+        }
+        errors
+    }
+    /// The game so far as a replayable record (see GameRecord), or None
+    /// for games restored from their final state.
+    pub fn game_record(&self) -> Option<String> {
+        self.record.as_ref().map(|r| r.to_string())
+    }
+    /// The game so far as an animated SVG (see render::replay_svg), or
+    /// None for games restored from their final state.
+    pub fn replay_svg(&self, step_secs: f32) -> Option<String> {
+        let record = self.record.as_ref()?;
+        render::replay_svg(record, &SvgOptions::default(), step_secs).ok()
+    }
+    /// Take exactly one action for the current AI player, if any.
+    /// Returns false if no AI action was pending.
+    pub fn step_agent<F: FnMut(&str, &str)>(
+        &mut self,
+        notice_cb: F,
+    ) -> Result<bool> {
+        if self.game_over {
+            return Ok(false);
+        }
+        let Some(ai) = &mut self.agents[self.state.curr_player_idx] else {
+            return Ok(false);
+        };
+        let action = ai.choose_action(&self.state, &mut rand::rng());
+        self.do_action(&action, notice_cb)?;
+        Ok(true)
+    }
+    fn player_idx(&self, player_id: &str) -> Result<usize> {
+        Ok(self
+            .player_ids
+            .iter()
+            .position(|id| id == player_id)
+            .ok_or("Unknown player ID")?)
+    }
+    /// Remove a player from the game, e.g. after they disconnect.
+    pub fn resign<F: FnMut(&str, &str)>(
+        &mut self,
+        player_id: &str,
+        mut notice_cb: F,
+    ) -> Result<()> {
+        if self.game_over {
+            return Err("Game is over".into());
+        }
+        let player_idx = self.player_idx(player_id)?;
+        let round_idx = self.state.round_idx;
+        let outcome = self.state.resign(player_idx, &mut self.rng)?;
+        if let Some(record) = &mut self.record {
+            record.add(round_idx, player_idx, RecordedAction::Resign);
+        }
+        if let ActionOutcome::GameOver = outcome {
+            self.game_over = true;
+            self.notify_game_end();
+        }
+        for idx in self.human_player_idxs() {
+            notice_cb(self.player_ids[idx].as_str(), self.view(idx)?.as_str());
+        }
+        // Advance to wait for the next player action.
+        self.process_agents(notice_cb)
+    }
+    /// Hand a player's seat to an AI, or back to a human if `agent` is None.
+    pub fn set_agent<F: FnMut(&str, &str)>(
+        &mut self,
+        player_id: &str,
+        agent: Option<&AgentSpec>,
+        mut notice_cb: F,
+    ) -> Result<()> {
+        if self.game_over {
+            return Err("Game is over".into());
+        }
+        let player_idx = self.player_idx(player_id)?;
+        let spec = agent.map(AgentSpec::for_api).transpose()?;
+        self.agents[player_idx] = spec.map(|spec| {
+            let mut agent = spec.create();
+            agent.on_game_start(&self.state, player_idx);
+            agent
+        });
+        for idx in self.human_player_idxs() {
+            notice_cb(self.player_ids[idx].as_str(), self.view(idx)?.as_str());
+        }
+        // Advance to wait for the next player action.
+        self.process_agents(notice_cb)
+    }
 }
+
 impl GameAPI for DurangoAPI {
     fn init(players: &[PlayerInfo], params: Option<&str>) -> Result<Self> {
         let params: GameParams = match params {
             Some(p) => serde_json::from_str(p)?,
             None => GameParams {
                 named_layout: "easy1".to_string(),
+                expansions: vec![],
+                catalog: None,
+                market: MarketSetup::Standard,
+                rules: RuleSet::default(),
+                step_agents: false,
+                agents: HashMap::new(),
+                seed: None,
             },
         };
-        let mut rng = rand::rng();
-        let state =
-            GameState::new(players.len(), &params.named_layout, &mut rng)?;
-        let player_ids = players.iter().map(|p| p.id.clone()).collect();
-        // API level to agent difficulty mapping:
-        // 0 => GreedyAgent
-        // 1 => StaticDistanceTurnPlanner(1)
-        // 2 => DynamicCostTurnPlanner
-        const AI_LVL_MAPPING: &[usize] = &[1, 3, 5];
-        let agents = players
+        let player_ids: Vec<String> =
+            players.iter().map(|p| p.id.clone()).collect();
+        // Agent specs from params take priority, then API levels (see
+        // AgentSpec::from_api_level), otherwise the player is human.
+        let specs = players
             .iter()
-            .map(|p| p.level.map(|i| create_agent(AI_LVL_MAPPING[i as usize])))
+            .map(|p| match (params.agents.get(&p.id), p.level) {
+                (Some(spec), _) => Ok(Some(spec.for_api()?)),
+                (None, Some(lvl)) => Ok(Some(AgentSpec::from_api_level(lvl)?)),
+                (None, None) => Ok(None),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let agents = specs
+            .iter()
+            .map(|spec| spec.as_ref().map(AgentSpec::create))
             .collect();
+        // The record sets up the game, so it can be replayed exactly.
+        let seed = params.seed.unwrap_or_else(|| rand::rng().random());
+        let mut record = GameRecord::new(
+            &params.named_layout,
+            seed,
+            player_ids.clone(),
+            params.rules,
+        );
+        record.agents = specs;
+        record.expansions = params.expansions;
+        record.market = params.market;
+        record.catalog = params.catalog;
+        let (state, rng) = record.start_game()?;
         let history = state
             .player_positions()
             .into_iter()
@@ -226,6 +341,9 @@ impl GameAPI for DurangoAPI {
             history,
             game_over: false,
             named_layout: params.named_layout,
+            step_agents: params.step_agents,
+            rng,
+            record: Some(record),
         })
     }
 
@@ -235,16 +353,28 @@ impl GameAPI for DurangoAPI {
             .players
             .into_iter()
             .map(|fp| {
-                Player::from_parts(fp.position, fp.tokens, fp.broken_barriers)
+                let mut p = Player::from_parts(
+                    fp.position,
+                    fp.tokens,
+                    fp.broken_barriers,
+                );
+                p.resigned = fp.resigned;
+                p
             })
             .collect();
+        let mut state = GameState::from_parts(fs.map, players, fs.round_idx);
+        state.end_reason = fs.end_reason;
+        state.rehash();
         Ok(Self {
-            state: GameState::from_parts(fs.map, players, fs.round_idx),
+            state,
             player_ids: player_info.iter().map(|p| p.id.clone()).collect(),
             agents: vec![],
             history: fs.history,
             game_over: true,
             named_layout: fs.named_layout,
+            step_agents: false,
+            rng: StdRng::from_rng(&mut rand::rng()),
+            record: None,
         })
     }
 
@@ -253,6 +383,11 @@ impl GameAPI for DurangoAPI {
         game_id: i64,
         mut notice_cb: F,
     ) -> Result<()> {
+        for (idx, agent) in self.agents.iter_mut().enumerate() {
+            if let Some(agent) = agent {
+                agent.on_game_start(&self.state, idx);
+            }
+        }
         let msg = format!(r#"{{"action": "start", "game_id": {game_id}}}"#);
         for idx in self.human_player_idxs() {
             notice_cb(self.player_ids[idx].as_str(), &msg);
@@ -270,6 +405,10 @@ impl GameAPI for DurangoAPI {
         if self.game_over {
             return Err("Game is over".into());
         }
+        // AI seats only act through their agent, e.g. via step_agent.
+        if self.agents[self.state.curr_player_idx].is_some() {
+            return Err("Current player is an AI".into());
+        }
         let action: PlayerAction = serde_json::from_str(action)?;
         self.do_action(&action, &mut notice_cb)?;
         // Advance to wait for the next player action.
@@ -304,26 +443,23 @@ impl DynSafeGameAPI for DurangoAPI {
                     .collect(),
                 tokens: p.tokens.clone(),
                 broken_barriers: p.broken_barriers.clone(),
+                resigned: p.resigned,
             })
             .collect();
         let fs = FinalState {
-            map: self.state.map.clone(),
+            map: self.state.map.as_ref().clone(),
             players,
             round_idx: self.state.round_idx,
             named_layout: self.named_layout.clone(),
             scores: self.state.player_scores(),
             history: self.history.clone(),
+            end_reason: self.state.end_reason,
         };
         Ok(serde_json::to_string(&fs)?)
     }
 
     fn player_view(&self, player_id: &str) -> Result<String> {
-        let player_idx = self
-            .player_ids
-            .iter()
-            .position(|id| id == player_id)
-            .ok_or("Unknown player ID")?;
-        self.view(player_idx)
+        self.view(self.player_idx(player_id)?)
     }
 
     fn current_player_id(&self) -> &str {
@@ -367,6 +503,127 @@ fn exercise_api() {
     );
 }
 
+#[test]
+fn agent_specs() {
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::human("bar".into()),
+    ];
+    let game: DurangoAPI = GameAPI::init(
+        &players,
+        Some(r#"{"named_layout": "first", "agents": {"bar": {"kind": "static", "cost_exponent": 2}}}"#),
+    )
+    .unwrap();
+    assert!(game.agents[0].is_none());
+    assert!(game.agents[1].is_some());
+    // Unknown kinds and levels are rejected.
+    let res: Result<DurangoAPI> = GameAPI::init(
+        &players,
+        Some(
+            r#"{"named_layout": "first", "agents": {"bar": {"kind": "genius"}}}"#,
+        ),
+    );
+    assert!(res.is_err());
+    // Unbounded searches are capped, and fair agents can't be nested.
+    let game: DurangoAPI = GameAPI::init(
+        &players,
+        Some(
+            r#"{"named_layout": "first", "agents": {"bar": {"kind": "dynamic", "budget": {"max_iterations": null, "max_time_ms": 999999999}}}}"#,
+        ),
+    )
+    .unwrap();
+    let Some(AgentSpec::Dynamic { budget, .. }) =
+        &game.record.as_ref().unwrap().agents[1]
+    else {
+        panic!("Expected a dynamic agent");
+    };
+    assert_eq!(*budget, crate::agent::MAX_API_BUDGET);
+    let res: Result<DurangoAPI> = GameAPI::init(
+        &players,
+        Some(
+            r#"{"named_layout": "first", "agents": {"bar": {"kind": "fair", "agent": {"kind": "fair", "agent": {"kind": "greedy"}}}}}"#,
+        ),
+    );
+    assert!(res.is_err());
+    // Clients can't make the server run commands, even nested in a spec.
+    for spec in [
+        r#"{"kind": "external", "command": ["sh", "-c", "true"]}"#,
+        r#"{"kind": "fair", "agent": {"kind": "external", "command": ["true"]}}"#,
+    ] {
+        let params = format!(
+            r#"{{"named_layout": "first", "agents": {{"bar": {spec}}}}}"#
+        );
+        let res: Result<DurangoAPI> = GameAPI::init(&players, Some(&params));
+        assert!(res.is_err());
+    }
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::ai("bot".into(), 3),
+    ];
+    let res: Result<DurangoAPI> = GameAPI::init(&players, None);
+    assert!(res.is_err());
+}
+
+#[test]
+fn step_agents() {
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::ai("bot".into(), 0),
+    ];
+    let mut game: DurangoAPI = GameAPI::init(
+        &players,
+        Some(r#"{"named_layout": "first", "step_agents": true}"#),
+    )
+    .unwrap();
+    game.start(1234, |_, _| {}).unwrap();
+    assert_eq!(game.pending_agent(), None);
+    game.process_action("\"FinishTurn\"", |_, _| {}).unwrap();
+    // The bot waits until we step it, and can't be played for.
+    assert_eq!(game.pending_agent(), Some("bot"));
+    assert!(game.process_action("\"FinishTurn\"", |_, _| {}).is_err());
+    let mut num_steps = 0;
+    while game.step_agent(|_, _| {}).unwrap() {
+        num_steps += 1;
+    }
+    assert!(num_steps >= 1);
+    assert_eq!(game.pending_agent(), None);
+    assert_eq!(game.current_player_id(), "foo");
+}
+
+#[test]
+fn resign_and_takeover() {
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::human("bar".into()),
+        PlayerInfo::human("baz".into()),
+    ];
+    let mut game: DurangoAPI =
+        GameAPI::init(&players, Some(r#"{"named_layout": "easy1"}"#)).unwrap();
+    game.start(1234, |_, _| {}).unwrap();
+    assert_eq!(game.current_player_id(), "foo");
+    // A bot takes over foo's seat and plays foo's turn.
+    game.set_agent("foo", Some(&AgentSpec::Greedy), |_, _| {})
+        .unwrap();
+    assert_eq!(game.current_player_id(), "bar");
+    let external: AgentSpec =
+        serde_json::from_str(r#"{"kind": "external", "command": ["true"]}"#)
+            .unwrap();
+    assert!(game.set_agent("bar", Some(&external), |_, _| {}).is_err());
+    // Bar drops out, so it's baz's turn.
+    game.resign("bar", |_, _| {}).unwrap();
+    assert_eq!(game.current_player_id(), "baz");
+    assert!(!game.is_game_over());
+    // Foo reclaims the seat before their next turn.
+    game.set_agent("foo", None, |_, _| {}).unwrap();
+    game.process_action("\"FinishTurn\"", |_, _| {}).unwrap();
+    assert_eq!(game.current_player_id(), "foo");
+    // Foo resigns, leaving baz as the winner.
+    game.resign("foo", |_, _| {}).unwrap();
+    assert!(game.is_game_over());
+    let scores = game.player_scores();
+    assert!(scores[2] > scores[0] && scores[2] > scores[1]);
+}
+
 #[test]
 fn self_play() {
     let players = vec![
@@ -378,6 +635,7 @@ fn self_play() {
     // Run until game over
     game.start(1234, |_, _| {}).unwrap();
     assert!(game.is_game_over());
+    assert!(game.take_agent_errors().is_empty());
     // Check that the history matches the final positions
     let final_positions = game.state.player_positions();
     for (idx, pos) in final_positions.iter().enumerate() {
@@ -394,3 +652,35 @@ fn self_play() {
         GameAPI::restore(&players, &final_state).unwrap();
     assert_eq!(restored_game.state.player_positions(), final_positions);
 }
+
+#[test]
+fn custom_catalog() {
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::human("bar".into()),
+    ];
+    let params = |catalog: &str| {
+        format!(r#"{{"named_layout": "first", "catalog": {catalog}}}"#)
+    };
+    let scout = r#"{"name": "Scout", "cost": 2, "movement": [2, 0, 0], "quantity": 3, "shop": true}"#;
+    let res: Result<DurangoAPI> =
+        GameAPI::init(&players, Some(&params(&format!("[{scout}]"))));
+    assert!(res.is_ok());
+    // Custom catalogs are checked like the built-in ones.
+    let res: Result<DurangoAPI> =
+        GameAPI::init(&players, Some(&params(&format!("[{scout}, {scout}]"))));
+    assert!(res.is_err());
+    let unnamed =
+        r#"{"cost": 2, "movement": [2, 0, 0], "quantity": 3, "shop": true}"#;
+    let res: Result<DurangoAPI> =
+        GameAPI::init(&players, Some(&params(&format!("[{unnamed}]"))));
+    assert!(res.is_err());
+    // Each expansion can only be added once.
+    let res: Result<DurangoAPI> = GameAPI::init(
+        &players,
+        Some(
+            r#"{"named_layout": "first", "expansions": ["HeroesAndHexes", "HeroesAndHexes"]}"#,
+        ),
+    );
+    assert!(res.is_err());
+}
@@ -1,13 +1,51 @@
+use crate::data::BonusToken;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[derive(
-    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord,
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash,
 )]
 pub enum CardAction {
     FreeBuy,
     FreeMove,
     Draw(usize),
     DrawAndTrash(usize),
+    // Expansion actions:
+    // Each opponent discards up to N random cards from their hand.
+    Sabotage(usize),
+    // Gain the given bonus token.
+    GainToken(BonusToken),
+    // Each opponent gains a Curse card in their discard pile.
+    Hex,
+    // Does nothing, but takes up space in the deck.
+    Curse,
+}
+impl CardAction {
+    /// Can this action be played on its own, via PlayerAction::Draw?
+    pub fn is_playable(&self) -> bool {
+        matches!(
+            self,
+            CardAction::Draw(_)
+                | CardAction::DrawAndTrash(_)
+                | CardAction::Sabotage(_)
+                | CardAction::GainToken(_)
+                | CardAction::Hex
+        )
+    }
+}
+
+/// Optional card packs that extend the base game.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expansion {
+    HeroesAndHexes,
+}
+impl Expansion {
+    /// Name of the catalog holding this expansion's cards.
+    pub fn catalog_name(&self) -> &'static str {
+        match self {
+            Expansion::HeroesAndHexes => "heroes_and_hexes",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -19,64 +57,44 @@ pub struct BuyableCard {
 }
 
 impl BuyableCard {
-    pub fn regular(cost: u8, movement: [u8; 3]) -> Self {
-        Self {
-            cost,
-            card: Card {
-                movement,
-                single_use: false,
-                action: None,
-            },
-            quantity: 3,
-        }
-    }
-    pub fn single_use(cost: u8, movement: [u8; 3]) -> Self {
-        Self {
-            cost,
-            card: Card {
-                movement,
-                single_use: true,
-                action: None,
-            },
-            quantity: 3,
-        }
-    }
-    pub fn action(cost: u8, action: CardAction, single_use: bool) -> Self {
-        Self {
-            cost,
-            card: Card {
-                movement: [0, 0, 0],
-                single_use,
-                action: Some(action),
-            },
-            quantity: 3,
-        }
-    }
     /// Convert to a regular Card, after purchase.
     pub fn to_card(&self) -> Card {
         self.card.clone()
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Serialize, Deserialize, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
 pub struct Card {
     // [Jungle, Desert, Water]
+    #[serde(default)]
     pub movement: [u8; 3],
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub single_use: bool,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub action: Option<CardAction>,
+    // Stable display name, e.g. "Pioneer". Shared to keep clones cheap.
+    #[serde(default)]
+    pub name: Arc<str>,
 }
 
 impl Card {
     pub fn gold_value(&self) -> u8 {
+        if self.is_curse() {
+            return 0;
+        }
         1.max(2 * self.movement[1])
     }
+    pub fn is_curse(&self) -> bool {
+        matches!(self.action, Some(CardAction::Curse))
+    }
     pub fn explorer() -> Self {
         Self {
             movement: [1, 0, 0],
             single_use: false,
             action: None,
+            name: "Explorer".into(),
         }
     }
     pub fn traveler() -> Self {
@@ -84,6 +102,7 @@ impl Card {
             movement: [0, 1, 0],
             single_use: false,
             action: None,
+            name: "Traveler".into(),
         }
     }
     pub fn sailor() -> Self {
@@ -91,27 +110,37 @@ impl Card {
             movement: [0, 0, 1],
             single_use: false,
             action: None,
+            name: "Sailor".into(),
+        }
+    }
+    pub fn curse() -> Self {
+        Self {
+            movement: [0, 0, 0],
+            single_use: false,
+            action: Some(CardAction::Curse),
+            name: "Curse".into(),
         }
     }
 }
 impl std::fmt::Debug for Card {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        if self.movement[0] > 0 {
-            write!(f, "J{}", self.movement[0])?;
-        }
-        if self.movement[1] > 0 {
-            write!(f, "D{}", self.movement[1])?;
-        }
-        if self.movement[2] > 0 {
-            write!(f, "W{}", self.movement[2])?;
+        let mut parts = Vec::new();
+        for (sym, &m) in ["J", "D", "W"].iter().zip(self.movement.iter()) {
+            if m > 0 {
+                parts.push(format!("{sym}{m}"));
+            }
         }
         if self.single_use {
-            f.write_str(" (1x)")?;
+            parts.push("(1x)".into());
         }
         if let Some(a) = &self.action {
-            write!(f, " {:?}", a)?;
+            parts.push(format!("{a:?}"));
+        }
+        if self.name.is_empty() {
+            f.write_str(&parts.join(" "))
+        } else {
+            write!(f, "{} [{}]", self.name, parts.join(" "))
         }
-        Ok(())
     }
 }
 
@@ -125,6 +154,7 @@ mod tests {
             movement: [0, 1, 0],
             single_use: false,
             action: None,
+            ..Default::default()
         };
         assert_eq!(card.gold_value(), 2);
 
@@ -132,6 +162,7 @@ mod tests {
             movement: [0, 0, 1],
             single_use: false,
             action: None,
+            ..Default::default()
         };
         assert_eq!(card.gold_value(), 1);
 
@@ -139,7 +170,10 @@ mod tests {
             movement: [0, 5, 0],
             single_use: false,
             action: None,
+            ..Default::default()
         };
         assert_eq!(card.gold_value(), 10);
+
+        assert_eq!(Card::curse().gold_value(), 0);
     }
 }
@@ -0,0 +1,320 @@
+use crate::cards::BuyableCard;
+use rand::prelude::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+/// A buyable card definition, as stored in a catalog file.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CatalogEntry {
+    #[serde(flatten)]
+    pub card: BuyableCard,
+    // Does this card start in the shop (rather than the storage)?
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub shop: bool,
+}
+impl CatalogEntry {
+    pub fn name(&self) -> &str {
+        &self.card.card.name
+    }
+}
+
+/// Parse a catalog from its JSON representation.
+pub fn parse_catalog(
+    json: &str,
+) -> Result<Vec<CatalogEntry>, Box<dyn std::error::Error>> {
+    let entries: Vec<CatalogEntry> = serde_json::from_str(json)?;
+    validate_catalog(&entries)?;
+    Ok(entries)
+}
+
+/// Check that every entry has a unique name and a nonzero quantity.
+pub fn validate_catalog(entries: &[CatalogEntry]) -> Result<(), String> {
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.name().is_empty() {
+            return Err(format!("Catalog entry {i} has no name"));
+        }
+        if entry.card.quantity == 0 {
+            return Err(format!(
+                "Catalog entry {} has zero quantity",
+                entry.name()
+            ));
+        }
+        if entries[..i].iter().any(|e| e.name() == entry.name()) {
+            return Err(format!("Duplicate catalog entry: {}", entry.name()));
+        }
+    }
+    Ok(())
+}
+
+/// Read a catalog from a JSON file, in the same format as the built-in ones.
+pub fn read_catalog(
+    path: impl AsRef<std::path::Path>,
+) -> Result<Vec<CatalogEntry>, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Reading {}: {e}", path.display()))?;
+    parse_catalog(&json)
+}
+
+/// Load one of the built-in catalogs by name.
+pub fn load_catalog(
+    name: &str,
+) -> Result<Vec<CatalogEntry>, Box<dyn std::error::Error>> {
+    match name {
+        "base" => parse_catalog(include_str!("../catalogs/base.json")),
+        "heroes_and_hexes" => {
+            parse_catalog(include_str!("../catalogs/heroes_and_hexes.json"))
+        }
+        _ => Err(format!("Unknown catalog: {name}").into()),
+    }
+}
+
+/// The cards available for purchase at the start of a game.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Market {
+    pub shop: Vec<BuyableCard>,
+    pub storage: Vec<BuyableCard>,
+}
+impl Market {
+    /// Standard setup: entries marked as shop cards start in the shop, up to
+    /// `shop_size` of them. Any extras go into the storage.
+    pub fn from_catalog(entries: &[CatalogEntry], shop_size: usize) -> Self {
+        let mut market = Market::default();
+        for entry in entries {
+            if entry.shop {
+                market.shop.push(entry.card.clone());
+            } else {
+                market.storage.push(entry.card.clone());
+            }
+        }
+        market.shop.sort_by_key(|c| c.cost);
+        if market.shop.len() > shop_size {
+            market.storage.extend(market.shop.split_off(shop_size));
+        }
+        market.storage.sort_by_key(|c| c.cost);
+        market
+    }
+    /// Put the entries at `shop_idxs` in the shop, and the rest in storage.
+    fn from_shop_indices(
+        entries: &[CatalogEntry],
+        shop_idxs: &[usize],
+    ) -> Self {
+        let mut market = Market::default();
+        for (i, entry) in entries.iter().enumerate() {
+            if shop_idxs.contains(&i) {
+                market.shop.push(entry.card.clone());
+            } else {
+                market.storage.push(entry.card.clone());
+            }
+        }
+        market.shop.sort_by_key(|c| c.cost);
+        market.storage.sort_by_key(|c| c.cost);
+        market
+    }
+    /// Check that this market is usable for a new game. The shop must be
+    /// full, unless there aren't enough cards to fill it.
+    pub fn validate(&self, shop_size: usize) -> Result<(), String> {
+        if self.shop.is_empty() {
+            return Err("Shop cannot be empty".into());
+        }
+        let full_size = shop_size.min(self.shop.len() + self.storage.len());
+        if self.shop.len() != full_size {
+            return Err(format!(
+                "Shop must hold {full_size} cards, got {}",
+                self.shop.len()
+            ));
+        }
+        if !self.shop.is_sorted_by_key(|c| c.cost) {
+            return Err("Shop must be sorted by cost".into());
+        }
+        let all_cards = self.shop.iter().chain(self.storage.iter());
+        for (i, c) in all_cards.clone().enumerate() {
+            if c.quantity == 0 {
+                return Err(format!("Card {:?} is out of stock", c.card));
+            }
+            if all_cards.clone().take(i).any(|other| other.card == c.card) {
+                return Err(format!("Card {:?} appears twice", c.card));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How to split a catalog's cards between the shop and the storage.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub enum MarketSetup {
+    // Use the shop flags from the catalog, which must fill the shop.
+    #[default]
+    Standard,
+    // Pick random cards to fill the shop.
+    Random,
+    // Put the named cards in the shop. There must be exactly enough to fill
+    // it.
+    Custom(Vec<String>),
+    // Pick random cards to fill the shop, keeping at least one card for each
+    // movement type. Needs room for at least three shop cards, and a card
+    // for each movement type.
+    Balanced,
+}
+impl MarketSetup {
+    /// Build a validated market from the given catalog entries.
+    pub fn build(
+        &self,
+        entries: &[CatalogEntry],
+        max_shop_size: usize,
+        rng: &mut dyn rand::RngCore,
+    ) -> Result<Market, String> {
+        let shop_size = max_shop_size.min(entries.len());
+        let market = match self {
+            MarketSetup::Standard => Market::from_catalog(entries, shop_size),
+            MarketSetup::Random => {
+                let mut idxs = (0..entries.len()).collect::<Vec<_>>();
+                idxs.shuffle(rng);
+                idxs.truncate(shop_size);
+                Market::from_shop_indices(entries, &idxs)
+            }
+            MarketSetup::Custom(names) => {
+                if names.len() != shop_size {
+                    return Err(format!(
+                        "Custom shop needs {shop_size} cards, got {}",
+                        names.len()
+                    ));
+                }
+                let mut idxs = Vec::with_capacity(names.len());
+                for name in names {
+                    let idx = entries
+                        .iter()
+                        .position(|e| e.name() == name)
+                        .ok_or(format!("Unknown card: {name}"))?;
+                    if idxs.contains(&idx) {
+                        return Err(format!("Duplicate shop card: {name}"));
+                    }
+                    idxs.push(idx);
+                }
+                Market::from_shop_indices(entries, &idxs)
+            }
+            MarketSetup::Balanced => {
+                if shop_size < 3 {
+                    return Err(format!(
+                        "Balanced shop needs room for 3 cards, got {shop_size}"
+                    ));
+                }
+                let mut order = (0..entries.len()).collect::<Vec<_>>();
+                order.shuffle(rng);
+                let mut idxs = Vec::with_capacity(shop_size);
+                // First pick one card for each movement type.
+                for terrain in 0..3 {
+                    let &i = order
+                        .iter()
+                        .find(|&&i| {
+                            entries[i].card.card.movement[terrain] > 0
+                                && !idxs.contains(&i)
+                        })
+                        .ok_or(format!(
+                            "Balanced shop needs a card for movement type {terrain}"
+                        ))?;
+                    idxs.push(i);
+                }
+                // Then fill the rest of the shop at random.
+                for &i in &order {
+                    if idxs.len() >= shop_size {
+                        break;
+                    }
+                    if !idxs.contains(&i) {
+                        idxs.push(i);
+                    }
+                }
+                Market::from_shop_indices(entries, &idxs)
+            }
+        };
+        market.validate(max_shop_size)?;
+        Ok(market)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_catalog() {
+        let entries = load_catalog("base").unwrap();
+        assert_eq!(entries.len(), 18);
+        let market = Market::from_catalog(&entries, 6);
+        assert_eq!(market.shop.len(), 6);
+        assert_eq!(market.storage.len(), 12);
+        assert_eq!(&*market.shop[0].card.name, "Scout");
+        assert_eq!(&*market.storage[11].card.name, "Native");
+    }
+
+    #[test]
+    fn market_setups() {
+        let rng = &mut rand::rng();
+        let entries = load_catalog("base").unwrap();
+        let market = MarketSetup::Random.build(&entries, 6, rng).unwrap();
+        assert_eq!(market.shop.len(), 6);
+        assert_eq!(market.storage.len(), 12);
+
+        let market = MarketSetup::Balanced.build(&entries, 6, rng).unwrap();
+        assert_eq!(market.shop.len(), 6);
+        for terrain in 0..3 {
+            assert!(market.shop.iter().any(|c| c.card.movement[terrain] > 0));
+        }
+
+        let setup =
+            MarketSetup::Custom(vec!["Pioneer".into(), "Captain".into()]);
+        let market = setup.build(&entries, 2, rng).unwrap();
+        assert_eq!(market.shop.len(), 2);
+        assert_eq!(&*market.shop[0].card.name, "Captain");
+        assert_eq!(market.storage.len(), 16);
+        // Custom shops must be full.
+        assert!(setup.build(&entries, 6, rng).is_err());
+        assert!(MarketSetup::Balanced.build(&entries, 2, rng).is_err());
+
+        let setup = MarketSetup::Custom(vec!["Nobody".into()]);
+        assert!(setup.build(&entries, 6, rng).is_err());
+        let setup = MarketSetup::Custom(vec![]);
+        assert!(setup.build(&entries, 6, rng).is_err());
+
+        // Standard shops must be full too.
+        let standard = MarketSetup::Standard;
+        assert!(standard.build(&entries, 7, rng).is_err());
+        let market = standard.build(&entries[..4], 6, rng).unwrap();
+        assert_eq!(market.shop.len(), 4);
+        // Balanced shops need cards for every movement type.
+        let no_water: Vec<_> = entries
+            .iter()
+            .filter(|e| e.card.card.movement[2] == 0)
+            .cloned()
+            .collect();
+        assert!(MarketSetup::Balanced.build(&no_water, 6, rng).is_err());
+    }
+
+    #[test]
+    fn invalid_catalog() {
+        let dupes = r#"[
+            {"name": "Scout", "cost": 2, "movement": [2, 0, 0], "quantity": 3},
+            {"name": "Scout", "cost": 4, "movement": [3, 0, 0], "quantity": 3}
+        ]"#;
+        assert!(parse_catalog(dupes).is_err());
+        let unnamed = r#"[{"cost": 2, "movement": [2, 0, 0], "quantity": 3}]"#;
+        assert!(parse_catalog(unnamed).is_err());
+    }
+
+    #[test]
+    fn catalog_file() {
+        let path = std::env::temp_dir()
+            .join(format!("durango-catalog-{}.json", std::process::id()));
+        let json = r#"[
+            {"name": "Scout", "cost": 2, "movement": [2, 0, 0], "quantity": 3, "shop": true},
+            {"name": "Rower", "cost": 3, "movement": [0, 0, 2], "quantity": 2}
+        ]"#;
+        std::fs::write(&path, json).unwrap();
+        let entries = read_catalog(&path);
+        std::fs::remove_file(&path).unwrap();
+        let entries = entries.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].shop);
+        assert_eq!(entries[1].name(), "Rower");
+        assert!(read_catalog(&path).is_err());
+    }
+}
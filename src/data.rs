@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 pub fn load_from_csv<T: for<'de> Deserialize<'de>>(
     data: &str,
@@ -15,7 +16,16 @@ pub fn load_from_csv<T: for<'de> Deserialize<'de>>(
 }
 
 #[derive(
-    Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+    Serialize,
+    Deserialize,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
 )]
 pub struct AxialCoord {
     pub q: i32,
@@ -57,6 +67,24 @@ impl HexDirection {
             _ => panic!("Invalid index"),
         }
     }
+    /// Short name, as used in action notation: NE, E, SE, SW, W or NW.
+    pub fn abbrev(&self) -> &'static str {
+        match self {
+            Self::NorthEast => "NE",
+            Self::East => "E",
+            Self::SouthEast => "SE",
+            Self::SouthWest => "SW",
+            Self::West => "W",
+            Self::NorthWest => "NW",
+        }
+    }
+    /// Parse a short name (in any case).
+    pub fn from_abbrev(s: &str) -> Option<Self> {
+        ALL_DIRECTIONS
+            .iter()
+            .find(|d| d.abbrev().eq_ignore_ascii_case(s))
+            .copied()
+    }
     pub fn reverse(&self) -> Self {
         match self {
             Self::NorthEast => Self::SouthWest,
@@ -128,7 +156,18 @@ impl Terrain {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
 pub enum BonusToken {
     Jungle(u8),
     Desert(u8),
@@ -240,7 +279,7 @@ pub struct Barrier {
     // Edges where this barrier exists. This is technically redundant info, as
     // it can be derived from from_board and to_board, but it's convenient to
     // store it to avoid recomputation.
-    pub edges: Vec<(AxialCoord, HexDirection)>,
+    pub edges: Arc<[(AxialCoord, HexDirection)]>,
 }
 
 /// A broken barrier that a player has removed.
@@ -0,0 +1,182 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::Write;
+
+use crate::agent::{AgentSpec, legal_actions};
+use crate::features;
+use crate::game::{ActionOutcome, GameState, PlayerAction};
+use crate::rules::RuleSet;
+
+/// One decision from a self-play game, written as a line of JSON.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Record {
+    // Index of the game within the dataset.
+    pub game: usize,
+    pub player_idx: usize,
+    pub round_idx: usize,
+    // See features::encode, from the acting player's point of view.
+    pub features: Vec<f32>,
+    pub legal_actions: Vec<PlayerAction>,
+    pub action: PlayerAction,
+    // Index of `action` in `legal_actions`, if listed there. Agents may
+    // choose actions the list leaves out, like discarding only some cards.
+    pub action_idx: Option<usize>,
+    // Final result for the acting player: 1 for a win, 0 for a loss, and
+    // 0.5 for a draw (including games cut off by `max_actions`).
+    pub outcome: f32,
+}
+
+/// Settings for generating a self-play dataset.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SelfPlayConfig {
+    // One agent per seat. Agents rotate seats from game to game.
+    pub agents: Vec<AgentSpec>,
+    // Named layouts, used in turn.
+    pub layouts: Vec<String>,
+    pub games: usize,
+    pub rules: RuleSet,
+    // Games still running after this many actions are scored as draws.
+    pub max_actions: usize,
+    // If set, each game's random state is derived from this seed.
+    pub seed: Option<u64>,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        Self {
+            agents: vec![AgentSpec::Greedy, AgentSpec::Greedy],
+            layouts: vec!["easy1".into()],
+            games: 10,
+            rules: RuleSet::default(),
+            max_actions: 2000,
+            seed: None,
+        }
+    }
+}
+
+/// Play self-play games and write a `Record` per action to `out`, one JSON
+/// object per line. Returns the number of records written.
+pub fn generate(
+    config: &SelfPlayConfig,
+    out: &mut impl Write,
+) -> Result<usize, Box<dyn Error>> {
+    if config.agents.is_empty() || config.layouts.is_empty() {
+        return Err("Self-play needs at least one agent and layout".into());
+    }
+    let mut num_records = 0;
+    for game_idx in 0..config.games {
+        let mut rng = match config.seed {
+            Some(seed) => {
+                StdRng::seed_from_u64(seed.wrapping_add(game_idx as u64))
+            }
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        let records = play_game(config, game_idx, &mut rng)?;
+        for record in &records {
+            writeln!(out, "{}", serde_json::to_string(record)?)?;
+        }
+        num_records += records.len();
+    }
+    out.flush()?;
+    Ok(num_records)
+}
+
+fn play_game(
+    config: &SelfPlayConfig,
+    game_idx: usize,
+    rng: &mut StdRng,
+) -> Result<Vec<Record>, String> {
+    let num_players = config.agents.len();
+    let layout = &config.layouts[game_idx % config.layouts.len()];
+    let mut game = GameState::new(num_players, layout, &config.rules, rng)
+        .map_err(|e| e.to_string())?;
+    let mut agents: Vec<_> = (0..num_players)
+        .map(|seat| config.agents[(seat + game_idx) % num_players].create())
+        .collect();
+    for (i, agent) in agents.iter_mut().enumerate() {
+        agent.on_game_start(&game, i);
+    }
+    let mut records = Vec::new();
+    let mut finished = false;
+    for _ in 0..config.max_actions {
+        let seat = game.curr_player_idx;
+        let legal = legal_actions(&game);
+        let features = features::encode(&game, seat);
+        let round_idx = game.round_idx;
+        let action = agents[seat].choose_action(&game, rng);
+        let outcome = game
+            .process_action(&action, rng)
+            .map_err(|e| format!("Seat {seat} chose {action:?}: {e}"))?;
+        for agent in agents.iter_mut() {
+            agent.on_action(&game, seat, &action, &outcome);
+        }
+        // Actions have no equality, so compare them by their debug form.
+        let key = format!("{action:?}");
+        let action_idx = legal.iter().position(|a| format!("{a:?}") == key);
+        records.push(Record {
+            game: game_idx,
+            player_idx: seat,
+            round_idx,
+            features,
+            legal_actions: legal,
+            action,
+            action_idx,
+            outcome: 0.5,
+        });
+        if matches!(outcome, ActionOutcome::GameOver) {
+            finished = true;
+            break;
+        }
+    }
+    for agent in agents.iter_mut() {
+        agent.on_game_end(&game);
+    }
+    if finished {
+        let scores = game.player_scores();
+        let best = *scores.iter().max().unwrap();
+        let num_best = scores.iter().filter(|&&s| s == best).count();
+        for record in &mut records {
+            record.outcome = if scores[record.player_idx] < best {
+                0.0
+            } else if num_best == 1 {
+                1.0
+            } else {
+                0.5
+            };
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_play_records() {
+        let config = SelfPlayConfig {
+            agents: vec![AgentSpec::Greedy, AgentSpec::Random],
+            layouts: vec!["first".into()],
+            games: 2,
+            max_actions: 300,
+            seed: Some(5),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let num_records = generate(&config, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let records: Vec<Record> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), num_records);
+        for record in &records {
+            assert_eq!(record.features.len(), features::NUM_FEATURES);
+            assert!(!record.legal_actions.is_empty());
+        }
+        assert!(records.iter().any(|r| r.action_idx.is_some()));
+    }
+}
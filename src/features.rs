@@ -0,0 +1,314 @@
+use crate::cards::{Card, CardAction};
+use crate::data::{AxialCoord, BonusToken, Node, Terrain};
+use crate::game::GameState;
+use crate::player::Player;
+
+/// Bumped whenever the meaning or order of the features changes.
+pub const FEATURES_VERSION: u32 = 1;
+
+/// Radius (in hexes) of the patch of map encoded around the player.
+pub const PATCH_RADIUS: i32 = 3;
+const PATCH_HEXES: usize = (1 + 3 * PATCH_RADIUS * (PATCH_RADIUS + 1)) as usize;
+// Terrain one-hot (7), cost, occupied, bonus tokens, distance change.
+const HEX_FEATURES: usize = 11;
+const NUM_ACTION_KINDS: usize = 8;
+const NUM_TOKEN_KINDS: usize = 10;
+// Distances (2), tokens, barriers, and the card composition (see
+// `encode_cards`).
+const PLAYER_FEATURES: usize = 3 + NUM_TOKEN_KINDS + CARD_FEATURES;
+// Count, movement sums (3), action counts and single-use cards.
+const CARD_FEATURES: usize = 5 + NUM_ACTION_KINDS;
+// The hand's cards, plus its gold, trashes and can_buy.
+const HAND_FEATURES: usize = CARD_FEATURES + 3;
+// Best movement and cheapest cost per terrain, action cards available, and
+// whether storage is open.
+const SHOP_FEATURES: usize = 6 + NUM_ACTION_KINDS + 1;
+// Round and number of players.
+const GAME_FEATURES: usize = 2;
+
+/// Length of every encoded feature vector.
+pub const NUM_FEATURES: usize = PATCH_HEXES * HEX_FEATURES
+    + 2 * PLAYER_FEATURES
+    + HAND_FEATURES
+    + SHOP_FEATURES
+    + GAME_FEATURES;
+
+/// Encode the game from `player_idx`'s point of view as a fixed-length
+/// feature vector, for learning value and policy models. In order:
+///  - the map patch within PATCH_RADIUS of the player, in axial order
+///  - the player, then the leading opponent (zeros if none)
+///  - the player's hand
+///  - the shop
+///  - the round and number of players
+///
+/// Only information visible to the player is used: decks are described by
+/// their contents, not their order.
+pub fn encode(game: &GameState, player_idx: usize) -> Vec<f32> {
+    let mut out = Vec::with_capacity(NUM_FEATURES);
+    let me = &game.players[player_idx];
+    encode_patch(game, player_idx, &mut out);
+    encode_player(game, me, &mut out);
+    let leader = game
+        .player_scores()
+        .into_iter()
+        .enumerate()
+        .filter(|&(i, _)| i != player_idx)
+        .max_by_key(|&(_, score)| score)
+        .map(|(i, _)| &game.players[i]);
+    match leader {
+        Some(p) => encode_player(game, p, &mut out),
+        None => out.extend([0.0; PLAYER_FEATURES]),
+    }
+    encode_cards(me.hand.iter(), &mut out);
+    let gold = me.hand.iter().map(|c| c.gold_value()).sum::<u8>()
+        + me.tokens.iter().map(|t| t.gold_value()).sum::<u8>();
+    out.push(gold as f32);
+    out.push(me.trashes as f32);
+    out.push(me.can_buy as u8 as f32);
+    encode_shop(game, &mut out);
+    out.push(game.round_idx as f32);
+    out.push(game.num_players() as f32);
+    debug_assert_eq!(out.len(), NUM_FEATURES);
+    out
+}
+
+fn encode_patch(game: &GameState, player_idx: usize, out: &mut Vec<f32>) {
+    let center = game.players[player_idx].position;
+    let my_dist = hex_dist(game, center);
+    let others: Vec<AxialCoord> = game
+        .players
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != player_idx)
+        .map(|(_, p)| p.position)
+        .collect();
+    let bonuses = game.bonus_counts();
+    for dq in -PATCH_RADIUS..=PATCH_RADIUS {
+        let min_dr = (-PATCH_RADIUS).max(-dq - PATCH_RADIUS);
+        let max_dr = PATCH_RADIUS.min(-dq + PATCH_RADIUS);
+        for dr in min_dr..=max_dr {
+            let pos = AxialCoord {
+                q: center.q + dq,
+                r: center.r + dr,
+            };
+            let node = game.map.node_at(pos);
+            let mut terrain = [0.0; 7];
+            terrain[terrain_idx(node)] = 1.0;
+            out.extend(terrain);
+            out.push(node.map_or(0, |n| n.cost) as f32);
+            out.push(others.contains(&pos) as u8 as f32);
+            let num_bonuses = bonuses
+                .iter()
+                .find(|(p, _)| **p == pos)
+                .map_or(0, |&(_, n)| n);
+            out.push(num_bonuses as f32);
+            // How much closer to the finish this hex is, if reachable.
+            let delta = match (node, hex_dist(game, pos), my_dist) {
+                (Some(_), Some(d), Some(my_d)) => (my_d - d) as f32,
+                _ => 0.0,
+            };
+            out.push(delta);
+        }
+    }
+}
+
+fn encode_player(game: &GameState, player: &Player, out: &mut Vec<f32>) {
+    let max_dist = game.graph.max_dist.max(1) as f32;
+    let dist = hex_dist(game, player.position).map_or(1.0, |d| d as f32);
+    out.push(dist / max_dist);
+    // Distance weighted by each hex's movement cost.
+    let idx = game.map.node_idx(player.position).unwrap();
+    let board_idx = game.map.node_at_idx(idx).unwrap().board_idx;
+    let cost_dists =
+        game.graph
+            .distances_to_finish(&game.map, board_idx, |node: &Node| {
+                node.cost as f64
+            });
+    let cost_dist = cost_dists[idx].min(4.0 * max_dist as f64) as f32;
+    out.push(cost_dist / max_dist);
+    let mut tokens = [0.0; NUM_TOKEN_KINDS];
+    for token in &player.tokens {
+        let (kind, value) = token_kind(token);
+        tokens[kind] += value;
+    }
+    out.extend(tokens);
+    out.push(player.broken_barriers.len() as f32);
+    let cards = player.all_cards();
+    encode_cards(
+        cards
+            .iter()
+            .flat_map(|&(card, n)| std::iter::repeat_n(card, n)),
+        out,
+    );
+}
+
+fn encode_cards<'a>(cards: impl Iterator<Item = &'a Card>, out: &mut Vec<f32>) {
+    let mut count = 0.0;
+    let mut movement = [0.0; 3];
+    let mut actions = [0.0; NUM_ACTION_KINDS];
+    let mut single_use = 0.0;
+    for card in cards {
+        count += 1.0;
+        for (sum, &mv) in movement.iter_mut().zip(&card.movement) {
+            *sum += mv as f32;
+        }
+        if let Some(action) = &card.action {
+            actions[action_kind(action)] += 1.0;
+        }
+        if card.single_use {
+            single_use += 1.0;
+        }
+    }
+    out.push(count);
+    out.extend(movement);
+    out.extend(actions);
+    out.push(single_use);
+}
+
+fn encode_shop(game: &GameState, out: &mut Vec<f32>) {
+    let mut best_movement = [0.0f32; 3];
+    let mut min_cost = [0.0f32; 3];
+    let mut actions = [0.0; NUM_ACTION_KINDS];
+    for bcard in game.all_buyable_cards().filter(|c| c.quantity > 0) {
+        let card = &bcard.card;
+        for i in 0..3 {
+            let mv = card.movement[i] as f32;
+            if mv == 0.0 {
+                continue;
+            }
+            best_movement[i] = best_movement[i].max(mv);
+            let cost = bcard.cost as f32;
+            if min_cost[i] == 0.0 || cost < min_cost[i] {
+                min_cost[i] = cost;
+            }
+        }
+        if let Some(action) = &card.action {
+            actions[action_kind(action)] += bcard.quantity as f32;
+        }
+    }
+    out.extend(best_movement);
+    out.extend(min_cost);
+    out.extend(actions);
+    out.push(game.has_open_shop() as u8 as f32);
+}
+
+fn hex_dist(game: &GameState, pos: AxialCoord) -> Option<i32> {
+    let idx = game.map.node_idx(pos)?;
+    let dist = game.graph.dists[idx];
+    (dist < i32::MAX).then_some(dist)
+}
+
+fn terrain_idx(node: Option<&Node>) -> usize {
+    match node.map(|n| n.terrain) {
+        None | Some(Terrain::Invalid) => 0,
+        Some(Terrain::Jungle) => 1,
+        Some(Terrain::Desert) => 2,
+        Some(Terrain::Water) => 3,
+        Some(Terrain::Village) => 4,
+        Some(Terrain::Swamp) => 5,
+        Some(Terrain::Cave) => 6,
+    }
+}
+
+fn action_kind(action: &CardAction) -> usize {
+    match action {
+        CardAction::FreeMove => 0,
+        CardAction::Draw(_) => 1,
+        CardAction::DrawAndTrash(_) => 2,
+        CardAction::FreeBuy => 3,
+        CardAction::Sabotage(_) => 4,
+        CardAction::GainToken(_) => 5,
+        CardAction::Hex => 6,
+        CardAction::Curse => 7,
+    }
+}
+
+// Index and weight of a token: movement tokens count their value.
+fn token_kind(token: &BonusToken) -> (usize, f32) {
+    match *token {
+        BonusToken::Jungle(v) => (0, v as f32),
+        BonusToken::Desert(v) => (1, v as f32),
+        BonusToken::Water(v) => (2, v as f32),
+        BonusToken::DrawCard => (3, 1.0),
+        BonusToken::TrashCard => (4, 1.0),
+        BonusToken::ReplaceHand => (5, 1.0),
+        BonusToken::DoubleUse => (6, 1.0),
+        BonusToken::ShareHex => (7, 1.0),
+        BonusToken::FreeMove => (8, 1.0),
+        BonusToken::SwapSymbol => (9, 1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    // Start of each section, in encoding order.
+    const PATCH: usize = 0;
+    const ME: usize = PATCH + PATCH_HEXES * HEX_FEATURES;
+    const LEADER: usize = ME + PLAYER_FEATURES;
+    const HAND: usize = LEADER + PLAYER_FEATURES;
+    const SHOP: usize = HAND + HAND_FEATURES;
+    const GAME: usize = SHOP + SHOP_FEATURES;
+
+    #[test]
+    fn layout() {
+        assert_eq!(PATCH_HEXES, 37);
+        assert_eq!(
+            [ME, LEADER, HAND, SHOP, GAME, NUM_FEATURES],
+            [407, 433, 459, 475, 490, 492]
+        );
+        // Saved datasets depend on this: bump FEATURES_VERSION whenever the
+        // layout changes, then update the pinned pair.
+        assert_eq!((FEATURES_VERSION, NUM_FEATURES), (1, 492));
+    }
+
+    #[test]
+    fn known_values() {
+        let rng = &mut rand::rng();
+        let mut game =
+            GameState::new(2, "first", &RuleSet::default(), rng).unwrap();
+        let me = &mut game.players[0];
+        me.hand = vec![
+            Card {
+                movement: [0, 2, 0],
+                ..Default::default()
+            },
+            Card {
+                single_use: true,
+                action: Some(CardAction::Draw(1)),
+                ..Default::default()
+            },
+        ];
+        me.tokens = vec![BonusToken::Jungle(2), BonusToken::DrawCard];
+        me.trashes = 1;
+        me.can_buy = true;
+        let x = encode(&game, 0);
+        assert_eq!(x.len(), NUM_FEATURES);
+
+        // The player's own hex is in the middle of the patch, in axial
+        // order: 4 + 5 + 6 hexes before its column, and 3 above it.
+        let center = &x[PATCH + 18 * HEX_FEATURES..][..HEX_FEATURES];
+        let node = game.map.node_at(game.players[0].position).unwrap();
+        assert_eq!(center[terrain_idx(Some(node))], 1.0);
+        assert_eq!(center[..7].iter().sum::<f32>(), 1.0);
+        assert_eq!(center[7], node.cost as f32);
+        assert_eq!(center[8..], [0.0, 0.0, 0.0]);
+
+        // Tokens: two jungle movement and one draw.
+        let tokens = &x[ME + 2..][..NUM_TOKEN_KINDS];
+        assert_eq!(tokens, [2.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(x[ME + 2 + NUM_TOKEN_KINDS], 0.0); // no broken barriers
+
+        // The hand: 2 cards, 2 desert movement, a single-use Draw card,
+        // 5 gold, 1 trash, and can buy.
+        let hand = &x[HAND..SHOP];
+        assert_eq!(hand[..4], [2.0, 0.0, 2.0, 0.0]);
+        assert_eq!(hand[4..12], [0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(hand[12..], [1.0, 5.0, 1.0, 1.0]);
+
+        assert_eq!(x[GAME - 1], game.has_open_shop() as u8 as f32);
+        assert_eq!(x[GAME..], [0.0, 2.0]);
+    }
+}
@@ -1,11 +1,16 @@
-use crate::cards::{BuyableCard, CardAction};
+use crate::cards::{BuyableCard, Card, CardAction};
+use crate::catalog::{self, Market};
 use crate::data::{
     self, AxialCoord, Barrier, BonusToken, HexDirection, HexMap, Node, Terrain,
 };
 use crate::graph::HexGraph;
-use crate::player::Player;
+use crate::player::{Player, Refill, TurnEnd};
+use crate::rules::RuleSet;
+use crate::view::PlayerView;
+use crate::zobrist::{ZobristHash, hash_of};
 use rand::prelude::SliceRandom;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 const MOVE_TYPES: [&str; 3] = ["jungle", "desert", "water"];
 
@@ -16,14 +21,14 @@ pub enum BuyIndex {
     Storage(usize),
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BuyCardAction {
     pub cards: Vec<usize>,
     pub tokens: Vec<usize>,
     pub index: BuyIndex,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct MoveAction {
     pub cards: Vec<usize>,
     pub tokens: Vec<usize>,
@@ -70,13 +75,13 @@ impl MoveAction {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct DrawAction {
     pub card: Option<usize>,
     pub token: Option<usize>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum PlayerAction {
     BuyCard(BuyCardAction),
     Move(MoveAction),
@@ -93,10 +98,122 @@ pub enum ActionOutcome {
     GameOver,
 }
 
+/// The changes made to a `GameState` by `GameState::apply`.
+pub struct Undo {
+    // Changes in the order they were made.
+    changes: Vec<Change>,
+    curr_player_idx: usize,
+    round_idx: usize,
+    end_reason: Option<EndReason>,
+}
+
+// A single change to the game, with what's needed to reverse it.
+#[derive(Clone)]
+enum Change {
+    // Hand cards at these indices moved to the play area.
+    Played {
+        player: usize,
+        cards: Vec<usize>,
+    },
+    // Hand cards at these indices moved to the discard pile.
+    Discarded {
+        player: usize,
+        cards: Vec<usize>,
+    },
+    // Hand cards at these indices were trashed.
+    Trashed {
+        player: usize,
+        cards: Vec<usize>,
+        trashed: Vec<Card>,
+    },
+    // The whole hand was spent, with the last `num_played` cards of the play
+    // area coming from it.
+    SpentHand {
+        player: usize,
+        hand: Vec<Card>,
+        num_played: usize,
+    },
+    // Tokens at these indices were used up.
+    UsedTokens {
+        player: usize,
+        tokens: Vec<usize>,
+    },
+    Drew {
+        player: usize,
+        refill: Refill,
+    },
+    ReplacedHand {
+        player: usize,
+        num_cards: usize,
+        refill: Refill,
+    },
+    FinishedTurn {
+        player: usize,
+        end: TurnEnd,
+    },
+    // A card was added to the discard pile.
+    GainedCard {
+        player: usize,
+    },
+    // A token was added from a card.
+    GainedToken {
+        player: usize,
+    },
+    Trashes {
+        player: usize,
+        old: usize,
+    },
+    CanBuy {
+        player: usize,
+        old: bool,
+    },
+    Moved {
+        player: usize,
+        from: AxialCoord,
+        visited_caves: Vec<AxialCoord>,
+    },
+    BrokeBarrier {
+        player: usize,
+        idx: usize,
+        barrier: Barrier,
+    },
+    // The top token of the cave at this index of `bonuses` was taken.
+    VisitedCave {
+        player: usize,
+        cave: usize,
+    },
+    // A card moved from storage to the shop.
+    Restocked {
+        storage_idx: usize,
+        shop_idx: usize,
+    },
+    // One of a card was taken, with the card itself if it sold out.
+    TookCard {
+        index: BuyIndex,
+        sold_out: Option<BuyableCard>,
+    },
+    // A Curse card was handed out from the pile.
+    Cursed {
+        player: usize,
+    },
+}
+
+/// Why the game ended.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EndReason {
+    // A player reached the finish.
+    Finished,
+    // The round limit was reached, so players are ranked by their progress.
+    RoundLimit,
+    // All but one player resigned.
+    Resignation,
+}
+
 #[derive(Clone)]
 pub struct GameState {
-    pub map: HexMap,
-    pub graph: HexGraph,
+    // Static board data, shared between copies of the game.
+    pub map: Arc<HexMap>,
+    pub graph: Arc<HexGraph>,
     pub barriers: Vec<Barrier>,
     pub players: Vec<Player>,
     pub shop: Vec<BuyableCard>,
@@ -104,25 +221,94 @@ pub struct GameState {
     bonuses: Vec<(AxialCoord, Vec<BonusToken>)>,
     pub curr_player_idx: usize,
     pub round_idx: usize,
+    pub rules: Arc<RuleSet>,
+    // Curse cards left to hand out.
+    pub curses: usize,
+    // Set once the game is over.
+    pub end_reason: Option<EndReason>,
+    // Hashes of the market and board, updated as actions are processed.
+    hashes: StateHashes,
+    // Changes made by the action being applied, if it may be undone.
+    changes: Option<Vec<Change>>,
+}
+
+/// Hashes of the market and board, updated as cards are bought, barriers
+/// are broken and cave tokens are taken. Player hashes are kept by `Player`.
+#[derive(Clone, Default)]
+struct StateHashes {
+    market: ZobristHash,
+    board: ZobristHash,
+}
+
+// Market slots and board pieces, keeping equal items in different places
+// apart in the hash.
+const SHOP: u8 = 0;
+const STORAGE: u8 = 1;
+const BARRIER: u8 = 0;
+const CAVE_TOKEN: u8 = 1;
+
+fn barrier_key(barrier: &Barrier) -> (u8, usize, usize) {
+    (BARRIER, barrier.from_board, barrier.to_board)
+}
+
+fn market_key(pile: u8, card: &BuyableCard) -> (u8, &Card, u8) {
+    (pile, &card.card, card.quantity)
+}
+
+// Cave tokens are taken in order, so each key includes its stack position.
+fn cave_key(
+    pos: AxialCoord,
+    stack_idx: usize,
+    token: BonusToken,
+) -> (u8, AxialCoord, usize, BonusToken) {
+    (CAVE_TOKEN, pos, stack_idx, token)
 }
 
 impl GameState {
     pub fn new(
         num_players: usize,
         preset: &str,
+        rules: &RuleSet,
         rng: &mut dyn rand::RngCore,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        if !(2..=4).contains(&num_players) {
+        rules.validate()?;
+        if !(rules.min_players..=rules.max_players).contains(&num_players) {
             return Err(
                 format!("Invalid number of players: {num_players}").into()
             );
         }
         let map = HexMap::create_named(preset)?;
+        let num_boundaries = map.finish_idx as usize - 1;
+        if rules.barrier_types.len() < num_boundaries {
+            return Err(format!(
+                "Not enough barrier types ({} boundaries, but only {} types)",
+                num_boundaries,
+                rules.barrier_types.len()
+            )
+            .into());
+        }
+        let num_caves = map
+            .all_nodes()
+            .filter(|(_, node)| node.terrain == Terrain::Cave)
+            .count();
+        if num_caves * rules.tokens_per_cave > data::ALL_BONUS_TOKENS.len() {
+            return Err(format!(
+                "Not enough bonus tokens for {} per cave ({} caves, but only {} tokens)",
+                rules.tokens_per_cave,
+                num_caves,
+                data::ALL_BONUS_TOKENS.len()
+            )
+            .into());
+        }
         let graph = HexGraph::new(&map);
+        let market = Market::from_catalog(
+            &catalog::load_catalog("base")?,
+            rules.shop_size,
+        );
         // Set up the barriers between boards.
-        let mut barrier_types = data::ALL_BARRIER_TYPES.to_vec();
+        let mut barrier_types = rules.barrier_types.clone();
         barrier_types.shuffle(rng);
-        barrier_types.truncate(map.finish_idx as usize - 1);
+        barrier_types.truncate(num_boundaries);
         let barriers = barrier_types
             .into_iter()
             .enumerate()
@@ -131,7 +317,7 @@ impl GameState {
                 to_board: i + 1,
                 terrain,
                 cost,
-                edges: edges_between_boards(&map, &graph, i, i + 1),
+                edges: edges_between_boards(&map, &graph, i, i + 1).into(),
             })
             .collect();
         // Determine starting positions for players.
@@ -155,71 +341,38 @@ impl GameState {
             .take(num_players)
             .map(|start_idx| {
                 let start_pos = map.coord_at_idx(start_idx).unwrap();
-                Player::new(start_pos, rng)
+                Player::new(start_pos, rules, rng)
             })
             .collect();
         // Initialize cave bonuses.
         let mut all_tokens = data::ALL_BONUS_TOKENS.to_vec();
         all_tokens.shuffle(rng);
+        let per_cave = rules.tokens_per_cave;
         let bonuses = map
             .all_nodes()
-            .filter_map(|(pos, node)| {
-                if node.terrain == Terrain::Cave && all_tokens.len() >= 4 {
-                    Some((pos, all_tokens.split_off(all_tokens.len() - 4)))
-                } else {
-                    None
-                }
+            .filter(|(_, node)| node.terrain == Terrain::Cave)
+            .map(|(pos, _)| {
+                (pos, all_tokens.split_off(all_tokens.len() - per_cave))
             })
             .collect();
-        Ok(Self {
-            map,
-            graph,
+        let mut game = Self {
+            map: Arc::new(map),
+            graph: Arc::new(graph),
             barriers,
             players,
-            shop: vec![
-                // Scout
-                BuyableCard::regular(2, [2, 0, 0]),
-                // Jack of all trades
-                BuyableCard::regular(4, [1, 1, 1]),
-                // Photographer
-                BuyableCard::regular(4, [0, 2, 0]),
-                // Trailblazer
-                BuyableCard::regular(6, [3, 0, 0]),
-                // Treasure chest
-                BuyableCard::single_use(6, [0, 4, 0]),
-                // Transmitter
-                BuyableCard::action(8, CardAction::FreeBuy, true),
-            ],
-            storage: vec![
-                // Captain
-                BuyableCard::regular(4, [0, 0, 3]),
-                // Compass
-                BuyableCard::action(4, CardAction::Draw(3), true),
-                // Journalist
-                BuyableCard::regular(6, [0, 3, 0]),
-                // Giant Machete
-                BuyableCard::single_use(6, [6, 0, 0]),
-                // Travel log
-                BuyableCard::action(6, CardAction::DrawAndTrash(2), true),
-                // Adventurer
-                BuyableCard::regular(8, [2, 2, 2]),
-                // Propeller plane
-                BuyableCard::single_use(8, [4, 4, 4]),
-                // Cartographer
-                BuyableCard::action(8, CardAction::Draw(2), false),
-                // Scientist
-                BuyableCard::action(8, CardAction::DrawAndTrash(1), false),
-                // Millionaire
-                BuyableCard::regular(10, [0, 4, 0]),
-                // Pioneer
-                BuyableCard::regular(10, [5, 0, 0]),
-                // Native
-                BuyableCard::action(10, CardAction::FreeMove, false),
-            ],
+            shop: market.shop,
+            storage: market.storage,
             bonuses,
             curr_player_idx: 0,
             round_idx: 0,
-        })
+            rules: Arc::new(rules.clone()),
+            curses: rules.curses,
+            end_reason: None,
+            hashes: StateHashes::default(),
+            changes: None,
+        };
+        game.rehash();
+        Ok(game)
     }
 
     /// Assemble a minimum game state from its parts.
@@ -228,9 +381,9 @@ impl GameState {
         players: Vec<Player>,
         round_idx: usize,
     ) -> Self {
-        Self {
-            graph: HexGraph::new(&map),
-            map,
+        let mut game = Self {
+            graph: Arc::new(HexGraph::new(&map)),
+            map: Arc::new(map),
             barriers: vec![],
             players,
             shop: vec![],
@@ -238,7 +391,77 @@ impl GameState {
             bonuses: vec![],
             curr_player_idx: 0,
             round_idx,
+            rules: Arc::default(),
+            curses: 0,
+            end_reason: None,
+            hashes: StateHashes::default(),
+            changes: None,
+        };
+        game.rehash();
+        game
+    }
+
+    /// Assemble a game from an observed position, with empty caves for the
+    /// caller to fill in (see `view::ViewSampler`).
+    pub(crate) fn from_view(
+        view: &PlayerView,
+        map: Arc<HexMap>,
+        graph: Arc<HexGraph>,
+        players: Vec<Player>,
+    ) -> Self {
+        let mut game = Self {
+            map,
+            graph,
+            barriers: view.barriers.clone(),
+            players,
+            shop: view.shop.clone(),
+            storage: view.storage.clone(),
+            bonuses: view
+                .bonuses
+                .iter()
+                .map(|&(pos, _)| (pos, vec![]))
+                .collect(),
+            curr_player_idx: view.curr_player_idx,
+            round_idx: view.round_idx,
+            rules: Arc::new(view.rules.clone()),
+            curses: view.curses,
+            end_reason: view.end_reason,
+            hashes: StateHashes::default(),
+            changes: None,
+        };
+        game.rehash();
+        game
+    }
+
+    /// Cave tokens, for filling in hidden information. Call `rehash` after
+    /// changing them.
+    pub(crate) fn bonuses_mut(
+        &mut self,
+    ) -> &mut [(AxialCoord, Vec<BonusToken>)] {
+        &mut self.bonuses
+    }
+
+    /// A copy of this game with the information hidden from `observer`
+    /// re-sampled: the order of every deck, the contents of opponents' hands,
+    /// and the order of tokens in each cave.
+    pub fn determinize(
+        &self,
+        observer: usize,
+        rng: &mut dyn rand::RngCore,
+    ) -> Self {
+        let mut game = self.clone();
+        for (i, p) in game.players.iter_mut().enumerate() {
+            if i == observer {
+                p.shuffle_deck(rng);
+            } else {
+                p.redeal_hand(rng);
+            }
+        }
+        for (_, tokens) in game.bonuses.iter_mut() {
+            tokens.shuffle(rng);
         }
+        game.rehash();
+        game
     }
 
     /// The player whose turn it is.
@@ -266,10 +489,9 @@ impl GameState {
 
     /// Is the specified node occupied by a player other than the current player?
     pub fn is_occupied(&self, pos: AxialCoord) -> bool {
-        self.players
-            .iter()
-            .enumerate()
-            .any(|(i, p)| p.position == pos && i != self.curr_player_idx)
+        self.players.iter().enumerate().any(|(i, p)| {
+            p.position == pos && i != self.curr_player_idx && !p.resigned
+        })
     }
 
     /// Which players (if any) are on a finish hex?
@@ -292,6 +514,9 @@ impl GameState {
         self.players
             .iter()
             .map(|p| {
+                if p.resigned {
+                    return -1; // resigned players rank last
+                }
                 let pos_idx = self.map.node_idx(p.position).unwrap();
                 let remaining_dist = self.graph.dists[pos_idx];
                 let mut score = self.graph.max_dist - remaining_dist
@@ -310,6 +535,7 @@ impl GameState {
         action: &PlayerAction,
         rng: &mut dyn rand::RngCore,
     ) -> Result<ActionOutcome, String> {
+        self.check_indices(action)?;
         let mut outcome = ActionOutcome::Ok;
         match action {
             PlayerAction::BuyCard(buy) => self.handle_buy(buy)?,
@@ -321,25 +547,403 @@ impl GameState {
             PlayerAction::Draw(draw) => self.handle_draw(draw, rng)?,
             PlayerAction::Trash(trash) => self.handle_trash(trash)?,
             PlayerAction::Discard(cards) => {
-                self.players[self.curr_player_idx].discard_cards(cards);
+                self.discard_cards(self.curr_player_idx, cards);
             }
             PlayerAction::FinishTurn => {
-                self.players[self.curr_player_idx].finish_turn(rng);
-                self.curr_player_idx += 1;
-                if self.curr_player_idx == self.players.len() {
-                    self.round_idx += 1;
-                    self.curr_player_idx = 0;
-                    if self.any_finished_player() {
-                        return Ok(ActionOutcome::GameOver);
+                let player = self.curr_player_idx;
+                let end =
+                    self.players[player].finish_turn(self.rules.hand_size, rng);
+                self.record(Change::FinishedTurn { player, end });
+                return Ok(self.advance_turn());
+            }
+        }
+        Ok(outcome)
+    }
+
+    // Reject card, token and shop indices that are out of range or repeated,
+    // e.g. from hand-written or corrupted game records.
+    fn check_indices(&self, action: &PlayerAction) -> Result<(), String> {
+        let me = self.curr_player();
+        let (cards, tokens) = match action {
+            PlayerAction::Move(mv) => (mv.cards.clone(), mv.tokens.clone()),
+            PlayerAction::BuyCard(buy) => {
+                let num_slots = match buy.index {
+                    BuyIndex::Shop(_) => self.shop.len(),
+                    BuyIndex::Storage(_) => self.storage.len(),
+                };
+                let (BuyIndex::Shop(i) | BuyIndex::Storage(i)) = buy.index;
+                if i >= num_slots {
+                    return Err(format!(
+                        "Invalid buy index {i}, given {num_slots} cards"
+                    ));
+                }
+                (buy.cards.clone(), buy.tokens.clone())
+            }
+            PlayerAction::Draw(draw) => (
+                draw.card.into_iter().collect(),
+                draw.token.into_iter().collect(),
+            ),
+            PlayerAction::Trash(cards) | PlayerAction::Discard(cards) => {
+                (cards.clone(), vec![])
+            }
+            PlayerAction::FinishTurn => (vec![], vec![]),
+        };
+        for (kind, idxs, len) in [
+            ("card", cards, me.hand.len()),
+            ("token", tokens, me.tokens.len()),
+        ] {
+            for (n, &i) in idxs.iter().enumerate() {
+                if i >= len {
+                    return Err(format!(
+                        "Invalid {kind} index {i}, given {len} {kind}s"
+                    ));
+                }
+                if idxs[..n].contains(&i) {
+                    return Err(format!("Repeated {kind} index {i}"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Process `action` like `process_action`, also returning a record of
+    /// the changes it made, which `undo` can use to restore this game.
+    /// On error, the game is left unchanged.
+    pub fn apply(
+        &mut self,
+        action: &PlayerAction,
+        rng: &mut dyn rand::RngCore,
+    ) -> Result<(ActionOutcome, Undo), String> {
+        let curr_player_idx = self.curr_player_idx;
+        let round_idx = self.round_idx;
+        let end_reason = self.end_reason;
+        self.changes = Some(Vec::new());
+        let result = self.process_action(action, rng);
+        let undo = Undo {
+            changes: self.changes.take().unwrap_or_default(),
+            curr_player_idx,
+            round_idx,
+            end_reason,
+        };
+        match result {
+            Ok(outcome) => Ok((outcome, undo)),
+            Err(e) => {
+                self.undo(undo);
+                Err(e)
+            }
+        }
+    }
+
+    /// Revert the changes from an `apply` call. Undo records must be used in
+    /// the reverse order of the actions that created them.
+    pub fn undo(&mut self, undo: Undo) {
+        for change in undo.changes.into_iter().rev() {
+            self.revert(change);
+        }
+        self.curr_player_idx = undo.curr_player_idx;
+        self.round_idx = undo.round_idx;
+        self.end_reason = undo.end_reason;
+    }
+
+    fn revert(&mut self, change: Change) {
+        match change {
+            Change::Played { player, cards } => {
+                self.players[player].unmark_played(&cards);
+            }
+            Change::Discarded { player, cards } => {
+                self.players[player].undiscard_cards(&cards);
+            }
+            Change::Trashed {
+                player,
+                cards,
+                trashed,
+            } => {
+                self.players[player].untrash_cards(&cards, trashed);
+            }
+            Change::SpentHand {
+                player,
+                hand,
+                num_played,
+            } => {
+                self.players[player].unspend_hand(hand, num_played);
+            }
+            Change::UsedTokens { player, tokens } => {
+                self.players[player].untrash_tokens(&tokens);
+            }
+            Change::Drew { player, refill } => {
+                self.players[player].unfill_hand(refill);
+            }
+            Change::ReplacedHand {
+                player,
+                num_cards,
+                refill,
+            } => {
+                self.players[player].unreplace_hand(num_cards, refill);
+            }
+            Change::FinishedTurn { player, end } => {
+                self.players[player].unfinish_turn(end);
+            }
+            Change::GainedCard { player } => {
+                self.players[player].ungain_card();
+            }
+            Change::GainedToken { player } => {
+                self.players[player].ungain_token();
+            }
+            Change::Trashes { player, old } => {
+                self.players[player].trashes = old;
+            }
+            Change::CanBuy { player, old } => {
+                self.players[player].can_buy = old;
+            }
+            Change::Moved {
+                player,
+                from,
+                visited_caves,
+            } => {
+                self.players[player].unmove_to(from, visited_caves);
+            }
+            Change::BrokeBarrier {
+                player,
+                idx,
+                barrier,
+            } => {
+                self.players[player].broken_barriers.pop();
+                self.hashes.board.add(&barrier_key(&barrier));
+                self.barriers.push(barrier);
+                let last = self.barriers.len() - 1;
+                self.barriers.swap(idx, last);
+            }
+            Change::VisitedCave { player, cave } => {
+                if let Some(tok) = self.players[player].unvisit_cave() {
+                    let (pos, tokens) = &mut self.bonuses[cave];
+                    self.hashes.board.add(&cave_key(*pos, tokens.len(), tok));
+                    tokens.push(tok);
+                }
+            }
+            Change::Restocked {
+                storage_idx,
+                shop_idx,
+            } => {
+                let card = self.shop.remove(shop_idx);
+                self.hashes.market.remove(&market_key(SHOP, &card));
+                self.hashes.market.add(&market_key(STORAGE, &card));
+                self.storage.push(card);
+                let last = self.storage.len() - 1;
+                self.storage.swap(storage_idx, last);
+            }
+            Change::TookCard { index, sold_out } => {
+                let (BuyIndex::Shop(i) | BuyIndex::Storage(i)) = index;
+                let (pile, cards) = match index {
+                    BuyIndex::Shop(_) => (SHOP, &mut self.shop),
+                    BuyIndex::Storage(_) => (STORAGE, &mut self.storage),
+                };
+                if let Some(card) = sold_out {
+                    if pile == SHOP {
+                        cards.insert(i, card);
+                    } else {
+                        // Storage cards are removed with swap_remove.
+                        cards.push(card);
+                        let last = cards.len() - 1;
+                        cards.swap(i, last);
                     }
+                } else {
+                    self.hashes.market.remove(&market_key(pile, &cards[i]));
                 }
+                cards[i].quantity += 1;
+                self.hashes.market.add(&market_key(pile, &cards[i]));
+            }
+            Change::Cursed { player } => {
+                self.players[player].ungain_card();
+                self.curses += 1;
             }
         }
-        Ok(outcome)
+    }
+
+    // Note a change made by the action being applied.
+    fn record(&mut self, change: Change) {
+        if let Some(changes) = &mut self.changes {
+            changes.push(change);
+        }
+    }
+
+    fn play_cards(&mut self, player: usize, cards: &[usize]) {
+        self.players[player].mark_played(cards);
+        let cards = cards.to_vec();
+        self.record(Change::Played { player, cards });
+    }
+
+    fn discard_cards(&mut self, player: usize, cards: &[usize]) {
+        self.players[player].discard_cards(cards);
+        let cards = cards.to_vec();
+        self.record(Change::Discarded { player, cards });
+    }
+
+    fn trash_cards(&mut self, player: usize, cards: &[usize]) {
+        let trashed = self.players[player].trash_cards(cards);
+        let cards = cards.to_vec();
+        self.record(Change::Trashed {
+            player,
+            cards,
+            trashed,
+        });
+    }
+
+    fn use_tokens(&mut self, player: usize, tokens: &[usize]) {
+        self.players[player].trash_tokens(tokens);
+        let tokens = tokens.to_vec();
+        self.record(Change::UsedTokens { player, tokens });
+    }
+
+    fn draw_cards(
+        &mut self,
+        player: usize,
+        hand_size: usize,
+        rng: &mut dyn rand::RngCore,
+    ) {
+        let refill = self.players[player].fill_hand(hand_size, rng);
+        self.record(Change::Drew { player, refill });
+    }
+
+    fn set_trashes(&mut self, player: usize, trashes: usize) {
+        let old = self.players[player].trashes;
+        self.players[player].trashes = trashes;
+        self.record(Change::Trashes { player, old });
+    }
+
+    // Take one of the card at `index`, removing it once it sells out.
+    fn take_card(&mut self, index: BuyIndex) {
+        let (BuyIndex::Shop(i) | BuyIndex::Storage(i)) = index;
+        let (pile, cards) = match index {
+            BuyIndex::Shop(_) => (SHOP, &mut self.shop),
+            BuyIndex::Storage(_) => (STORAGE, &mut self.storage),
+        };
+        self.hashes.market.remove(&market_key(pile, &cards[i]));
+        cards[i].quantity -= 1;
+        let sold_out = if cards[i].quantity > 0 {
+            self.hashes.market.add(&market_key(pile, &cards[i]));
+            None
+        } else if pile == SHOP {
+            // Keep the shop sorted by cost.
+            Some(cards.remove(i))
+        } else {
+            Some(cards.swap_remove(i))
+        };
+        self.record(Change::TookCard { index, sold_out });
+    }
+
+    /// A hash of the current position: player positions, the cards in each
+    /// player's hand, play area, deck and discard pile (as multisets), tokens,
+    /// the market, barriers, caves, the round and whose turn it is. Equal
+    /// positions reached through different action orders have equal hashes,
+    /// and hashes are the same across runs.
+    ///
+    /// Card piles, the market and the board are hashed Zobrist-style, with a
+    /// key for each card, token or barrier that is added and removed as they
+    /// move, so this takes constant time for a given number of players.
+    pub fn hash(&self) -> u64 {
+        let players = self.players.iter().enumerate().map(|(i, p)| {
+            hash_of(&(
+                i,
+                p.position,
+                p.piles_hash(),
+                p.trashes,
+                p.can_buy,
+                p.broken_barriers.len(),
+                p.resigned,
+            ))
+        });
+        let market = hash_of(&(self.hashes.market.value(), self.curses));
+        let turn = hash_of(&(
+            self.curr_player_idx,
+            self.round_idx,
+            self.end_reason.is_some(),
+        ));
+        players.fold(market ^ self.hashes.board.value() ^ turn, |h, p| h ^ p)
+    }
+
+    /// Recompute the hash from scratch. Only needed after changing public
+    /// fields directly, since processing actions keeps the hash up to date.
+    pub fn rehash(&mut self) {
+        for p in &mut self.players {
+            p.rehash();
+        }
+        let shop = self.shop.iter().map(|c| market_key(SHOP, c));
+        let storage = self.storage.iter().map(|c| market_key(STORAGE, c));
+        self.hashes.market = shop.chain(storage).collect();
+        let barriers = self.barriers.iter().map(barrier_key);
+        let mut board: ZobristHash = barriers.collect();
+        for (pos, tokens) in &self.bonuses {
+            for (i, &tok) in tokens.iter().enumerate() {
+                board.add(&cave_key(*pos, i, tok));
+            }
+        }
+        self.hashes.board = board;
+    }
+
+    /// Pass the turn to the next player who hasn't resigned.
+    fn advance_turn(&mut self) -> ActionOutcome {
+        loop {
+            self.curr_player_idx += 1;
+            if self.curr_player_idx == self.players.len() {
+                self.round_idx += 1;
+                self.curr_player_idx = 0;
+                if self.any_finished_player() {
+                    self.end_reason = Some(EndReason::Finished);
+                } else if let Some(max_rounds) = self.rules.max_rounds
+                    && self.round_idx >= max_rounds
+                {
+                    self.end_reason = Some(EndReason::RoundLimit);
+                }
+                if self.end_reason.is_some() {
+                    return ActionOutcome::GameOver;
+                }
+            }
+            if !self.players[self.curr_player_idx].resigned {
+                return ActionOutcome::Ok;
+            }
+        }
+    }
+
+    /// Remove a player from the game. Their piece no longer blocks other
+    /// players, and they rank last. If only one player remains, they win.
+    pub fn resign(
+        &mut self,
+        player_idx: usize,
+        rng: &mut dyn rand::RngCore,
+    ) -> Result<ActionOutcome, String> {
+        if self.end_reason.is_some() {
+            return Err("Game is over".into());
+        }
+        let player = self
+            .players
+            .get_mut(player_idx)
+            .ok_or(format!("Invalid player index {player_idx}"))?;
+        if player.resigned {
+            return Err(format!("Player {player_idx} already resigned"));
+        }
+        player.resigned = true;
+        if self.players.iter().filter(|p| !p.resigned).count() <= 1 {
+            self.end_reason = Some(EndReason::Resignation);
+            return Ok(ActionOutcome::GameOver);
+        }
+        if player_idx == self.curr_player_idx {
+            self.players[player_idx].finish_turn(self.rules.hand_size, rng);
+            return Ok(self.advance_turn());
+        }
+        Ok(ActionOutcome::Ok)
+    }
+
+    /// Replace the shop and storage, before the game starts.
+    pub fn set_market(&mut self, market: Market) -> Result<(), String> {
+        market.validate(self.rules.shop_size)?;
+        self.shop = market.shop;
+        self.storage = market.storage;
+        self.rehash();
+        Ok(())
     }
 
     pub fn has_open_shop(&self) -> bool {
-        self.shop.len() < 6
+        self.shop.len() < self.rules.shop_size
     }
 
     pub fn buyable_card(&self, idx: &BuyIndex) -> &BuyableCard {
@@ -407,16 +1011,10 @@ impl GameState {
             })
             .collect();
         let card = bcard.to_card();
+        let me = self.curr_player_idx;
         if is_free_buy {
             // Just take the card without moving from storage to shop.
-            match buy.index {
-                BuyIndex::Shop(i) => {
-                    take_card(&mut self.shop, i);
-                }
-                BuyIndex::Storage(i) => {
-                    take_card(&mut self.storage, i);
-                }
-            }
+            self.take_card(buy.index);
         } else {
             // Move the card from storage to shop if needed, then take it.
             let shop_idx = match buy.index {
@@ -427,39 +1025,54 @@ impl GameState {
                             "Cannot buy from storage while shop is full".into(),
                         );
                     }
-                    self.shop.push(self.storage.swap_remove(i));
-                    self.shop.len() - 1
+                    // Keep the shop sorted by cost.
+                    let restocked = self.storage.swap_remove(i);
+                    self.hashes.market.remove(&market_key(STORAGE, &restocked));
+                    self.hashes.market.add(&market_key(SHOP, &restocked));
+                    let shop_idx =
+                        self.shop.partition_point(|c| c.cost <= restocked.cost);
+                    self.shop.insert(shop_idx, restocked);
+                    self.record(Change::Restocked {
+                        storage_idx: i,
+                        shop_idx,
+                    });
+                    shop_idx
                 }
             };
-            take_card(&mut self.shop, shop_idx);
+            self.take_card(BuyIndex::Shop(shop_idx));
         }
         // Add the newly-bought card to the player's discard pile.
-        self.players[self.curr_player_idx].discard.push(card);
+        self.players[me].gain_card(card);
+        self.record(Change::GainedCard { player: me });
         // Discard or trash the cards used to pay for the purchase.
         if single_use_idxs.is_empty() {
-            self.players[self.curr_player_idx].mark_played(&buy.cards);
+            self.play_cards(me, &buy.cards);
         } else if single_use_idxs.len() == buy.cards.len() {
             // All cards used were single-use, so trash them all.
-            self.players[self.curr_player_idx].trash_cards(&buy.cards);
+            self.trash_cards(me, &buy.cards);
         } else {
             // We have a mix: some cards to trash, some to discard.
-            let p = &mut self.players[self.curr_player_idx];
-            for i in &buy.cards {
-                if !single_use_idxs.contains(i) {
-                    p.played.push(p.hand[*i].clone());
-                }
-            }
-            p.hand.clear();
+            let played: Vec<usize> = buy
+                .cards
+                .iter()
+                .filter(|i| !single_use_idxs.contains(i))
+                .cloned()
+                .collect();
+            let hand = self.players[me].spend_hand(&played);
+            self.record(Change::SpentHand {
+                player: me,
+                hand,
+                num_played: played.len(),
+            });
         }
         // Ensure we only buy one card per turn (excluding free buys).
         if !is_free_buy {
-            self.players[self.curr_player_idx].can_buy = false;
+            let old = self.players[me].can_buy;
+            self.players[me].can_buy = false;
+            self.record(Change::CanBuy { player: me, old });
         }
-        // Ensure the shop remains sorted by cost. We only ever remove from
-        // storage, so no need to re-sort that.
-        self.shop.sort_unstable_by_key(|c| c.cost);
         // Trash any used tokens.
-        self.players[self.curr_player_idx].trash_tokens(&buy.tokens);
+        self.use_tokens(me, &buy.tokens);
         Ok(())
     }
 
@@ -740,29 +1353,36 @@ impl GameState {
         }
 
         // Update the player's position and cards.
-        let player = &mut self.players[self.curr_player_idx];
-        player.position = pos;
+        let me = self.curr_player_idx;
+        let (from, visited_caves) = self.players[me].move_to(pos);
+        self.record(Change::Moved {
+            player: me,
+            from,
+            visited_caves,
+        });
         if is_single_use
             || (card_cost > 0
                 && self.map.with_terrain(pos, Terrain::Village).is_some())
         {
-            player.trash_cards(&mv.cards);
+            self.trash_cards(me, &mv.cards);
         } else {
-            player.mark_played(&mv.cards);
+            self.play_cards(me, &mv.cards);
         }
-        // Clear any visited caves that are no longer adjacent.
-        player
-            .visited_caves
-            .retain(|&cave_pos| pos.is_adjacent(cave_pos));
         // Trash any used tokens.
-        player.trash_tokens(&mv.tokens);
+        self.use_tokens(me, &mv.tokens);
         // Remove broken barriers from the game.
         if let Some(idx) = broken_barrier {
             let barrier = self.barriers.swap_remove(idx);
-            player.broken_barriers.push(data::BrokenBarrier {
+            self.hashes.board.remove(&barrier_key(&barrier));
+            self.players[me].broken_barriers.push(data::BrokenBarrier {
                 terrain: barrier.terrain,
                 cost: barrier.cost,
             });
+            self.record(Change::BrokeBarrier {
+                player: me,
+                idx,
+                barrier,
+            });
         }
         Ok(ignore_idx)
     }
@@ -794,15 +1414,24 @@ impl GameState {
                     ));
                 }
             }
+            let me = self.curr_player_idx;
             match card.action {
                 Some(CardAction::Draw(n)) => {
-                    self.players[self.curr_player_idx]
-                        .fill_hand(hand_size + n, rng);
+                    self.draw_cards(me, hand_size + n, rng);
                 }
                 Some(CardAction::DrawAndTrash(n)) => {
-                    self.players[self.curr_player_idx]
-                        .fill_hand(hand_size + n, rng);
-                    self.players[self.curr_player_idx].trashes += n;
+                    self.draw_cards(me, hand_size + n, rng);
+                    self.set_trashes(me, self.players[me].trashes + n);
+                }
+                Some(CardAction::Sabotage(n)) => {
+                    self.sabotage_opponents(n, rng);
+                }
+                Some(CardAction::GainToken(tok)) => {
+                    self.players[me].gain_token(tok);
+                    self.record(Change::GainedToken { player: me });
+                }
+                Some(CardAction::Hex) => {
+                    self.curse_opponents();
                 }
                 _ => {
                     return Err(format!(
@@ -811,27 +1440,33 @@ impl GameState {
                 }
             }
             if is_single_use {
-                self.players[self.curr_player_idx].trash_cards(&[i]);
+                self.trash_cards(me, &[i]);
             } else {
-                self.players[self.curr_player_idx].mark_played(&[i]);
+                self.play_cards(me, &[i]);
             }
             if let Some(tidx) = draw.token {
-                self.players[self.curr_player_idx].tokens.swap_remove(tidx);
+                self.use_tokens(me, &[tidx]);
             }
         } else if let Some(i) = draw.token {
             let tok = tokens.get(i).ok_or(format!(
                 "Invalid token index {i}, given {num_tokens} tokens"
             ))?;
+            let me = self.curr_player_idx;
             match tok {
                 BonusToken::DrawCard => {
-                    self.players[self.curr_player_idx]
-                        .fill_hand(hand_size + 1, rng);
+                    self.draw_cards(me, hand_size + 1, rng);
                 }
                 BonusToken::TrashCard => {
-                    self.players[self.curr_player_idx].trashes += 1;
+                    self.set_trashes(me, self.players[me].trashes + 1);
                 }
                 BonusToken::ReplaceHand => {
-                    self.players[self.curr_player_idx].replace_hand(rng);
+                    let (num_cards, refill) =
+                        self.players[me].replace_hand(rng);
+                    self.record(Change::ReplacedHand {
+                        player: me,
+                        num_cards,
+                        refill,
+                    });
                 }
                 _ => {
                     return Err(format!(
@@ -840,7 +1475,7 @@ impl GameState {
                 }
             }
             // Remove the used token.
-            self.players[self.curr_player_idx].tokens.swap_remove(i);
+            self.use_tokens(me, &[i]);
         } else {
             return Err(
                 "Must specify a card or token to use for drawing cards".into(),
@@ -849,6 +1484,38 @@ impl GameState {
         Ok(())
     }
 
+    /// Each opponent discards up to `n` random cards from their hand.
+    fn sabotage_opponents(&mut self, n: usize, rng: &mut dyn rand::RngCore) {
+        for i in 0..self.players.len() {
+            let p = &self.players[i];
+            if i == self.curr_player_idx || p.resigned {
+                continue;
+            }
+            let mut idxs = (0..p.hand.len()).collect::<Vec<_>>();
+            idxs.shuffle(rng);
+            idxs.truncate(n);
+            self.discard_cards(i, &idxs);
+        }
+    }
+
+    /// Each opponent gains a Curse card in their discard pile, in turn order,
+    /// until the curses run out.
+    fn curse_opponents(&mut self) {
+        let num_players = self.players.len();
+        for offset in 1..num_players {
+            if self.curses == 0 {
+                break;
+            }
+            let player = (self.curr_player_idx + offset) % num_players;
+            let p = &mut self.players[player];
+            if !p.resigned {
+                p.gain_card(Card::curse());
+                self.curses -= 1;
+                self.record(Change::Cursed { player });
+            }
+        }
+    }
+
     fn handle_trash(&mut self, trash: &[usize]) -> Result<(), String> {
         let num_to_trash = trash.len();
         let num_allowed = self.curr_player().trashes;
@@ -858,8 +1525,8 @@ impl GameState {
                 num_to_trash, num_allowed,
             ));
         }
-        self.players[self.curr_player_idx].trash_cards(trash);
-        self.players[self.curr_player_idx].trashes -= num_to_trash;
+        self.trash_cards(self.curr_player_idx, trash);
+        self.set_trashes(self.curr_player_idx, num_allowed - num_to_trash);
         Ok(())
     }
 
@@ -871,16 +1538,19 @@ impl GameState {
     }
 
     fn give_bonus(&mut self, pos: AxialCoord) -> Result<(), String> {
-        let tokens = self
+        let cave = self
             .bonuses
-            .iter_mut()
-            .find_map(|(p, tokens)| if *p == pos { Some(tokens) } else { None })
+            .iter()
+            .position(|(p, _)| *p == pos)
             .ok_or(format!("No cave at {pos:?}"))?;
+        let tokens = &mut self.bonuses[cave].1;
         let tok = tokens
             .pop()
             .ok_or(format!("No bonus tokens remaining in cave at {pos:?}"))?;
-        self.players[self.curr_player_idx].tokens.push(tok);
-        self.players[self.curr_player_idx].visited_caves.push(pos);
+        self.hashes.board.remove(&cave_key(pos, tokens.len(), tok));
+        let me = self.curr_player_idx;
+        self.players[me].visit_cave(pos, tok);
+        self.record(Change::VisitedCave { player: me, cave });
         Ok(())
     }
 
@@ -908,15 +1578,6 @@ impl GameState {
     }
 }
 
-fn take_card(cards: &mut Vec<BuyableCard>, idx: usize) {
-    if let Some(card) = cards.get_mut(idx) {
-        card.quantity -= 1;
-        if card.quantity == 0 {
-            cards.swap_remove(idx);
-        }
-    }
-}
-
 /// Finds all edges between two boards.
 fn edges_between_boards(
     map: &HexMap,
@@ -947,12 +1608,229 @@ fn edges_between_boards(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cards::Expansion;
+    use crate::record::GameRecord;
 
     #[test]
     fn initialization() {
-        let game = GameState::new(4, "easy1", &mut rand::rng()).unwrap();
+        let game =
+            GameState::new(4, "easy1", &RuleSet::default(), &mut rand::rng())
+                .unwrap();
         assert_eq!(game.players.len(), 4);
         assert_eq!(game.shop.len(), 6);
         assert_eq!(game.storage.len(), 12);
     }
+
+    #[test]
+    fn rules_must_fit_the_map() {
+        let rng = &mut rand::rng();
+        // Every boundary between boards needs a barrier.
+        let rules = RuleSet {
+            barrier_types: vec![(Terrain::Jungle, 1)],
+            ..Default::default()
+        };
+        assert!(GameState::new(2, "first", &rules, rng).is_err());
+        // Every cave needs a full set of tokens.
+        let rules = RuleSet {
+            tokens_per_cave: 13,
+            ..Default::default()
+        };
+        assert!(GameState::new(2, "first", &rules, rng).is_err());
+        let rules = RuleSet {
+            tokens_per_cave: 12,
+            ..Default::default()
+        };
+        assert!(GameState::new(2, "first", &rules, rng).is_ok());
+    }
+
+    #[test]
+    fn round_limit() {
+        let rng = &mut rand::rng();
+        let rules = RuleSet {
+            max_rounds: Some(2),
+            ..Default::default()
+        };
+        let mut game = GameState::new(2, "easy1", &rules, rng).unwrap();
+        for _ in 0..3 {
+            let outcome = game.process_action(&PlayerAction::FinishTurn, rng);
+            assert!(matches!(outcome, Ok(ActionOutcome::Ok)));
+        }
+        assert_eq!(game.end_reason, None);
+        let outcome = game.process_action(&PlayerAction::FinishTurn, rng);
+        assert!(matches!(outcome, Ok(ActionOutcome::GameOver)));
+        assert_eq!(game.end_reason, Some(EndReason::RoundLimit));
+        assert_eq!(game.round_idx, 2);
+    }
+
+    #[test]
+    fn resignation() {
+        let rng = &mut rand::rng();
+        let mut game =
+            GameState::new(3, "easy1", &RuleSet::default(), rng).unwrap();
+        // Resigning on your own turn passes the turn.
+        assert!(matches!(game.resign(0, rng), Ok(ActionOutcome::Ok)));
+        assert_eq!(game.curr_player_idx, 1);
+        assert!(game.resign(0, rng).is_err());
+        // Resigned players are skipped.
+        game.process_action(&PlayerAction::FinishTurn, rng).unwrap();
+        game.process_action(&PlayerAction::FinishTurn, rng).unwrap();
+        assert_eq!(game.curr_player_idx, 1);
+        assert_eq!(game.round_idx, 1);
+        // The last player standing wins.
+        let outcome = game.resign(1, rng);
+        assert!(matches!(outcome, Ok(ActionOutcome::GameOver)));
+        assert_eq!(game.end_reason, Some(EndReason::Resignation));
+        let scores = game.player_scores();
+        assert!(scores[2] > scores[0] && scores[2] > scores[1]);
+    }
+
+    #[test]
+    fn expansion_cards() {
+        let rng = &mut rand::rng();
+        let players = vec!["a".into(), "b".into(), "c".into()];
+        let mut record =
+            GameRecord::new("easy1", 0, players, RuleSet::default());
+        record.expansions = vec![Expansion::HeroesAndHexes];
+        let (mut game, _) = record.start_game().unwrap();
+        assert_eq!(game.storage.len(), 16);
+        record.expansions.push(Expansion::HeroesAndHexes);
+        assert!(record.start_game().is_err());
+        // Play a Hexer, which curses both opponents.
+        game.players[0].hand[0] = Card {
+            action: Some(CardAction::Hex),
+            ..Default::default()
+        };
+        let draw = PlayerAction::Draw(DrawAction {
+            card: Some(0),
+            token: None,
+        });
+        game.process_action(&draw, rng).unwrap();
+        assert_eq!(game.players[0].discard.len(), 0);
+        for p in &game.players[1..] {
+            assert_eq!(p.discard.len(), 1);
+            assert!(p.discard[0].is_curse());
+        }
+        // Play a Saboteur, which forces both opponents to discard.
+        game.players[0].hand[0].action = Some(CardAction::Sabotage(1));
+        game.process_action(&draw, rng).unwrap();
+        for p in &game.players[1..] {
+            assert_eq!(p.hand.len(), 3);
+            assert_eq!(p.discard.len(), 2);
+        }
+        // Resigned players are left alone.
+        game.players[2].resigned = true;
+        for action in [CardAction::Hex, CardAction::Sabotage(1)] {
+            game.players[0].hand = vec![Card {
+                action: Some(action),
+                ..Default::default()
+            }];
+            game.process_action(&draw, rng).unwrap();
+        }
+        assert_eq!(game.players[1].discard.len(), 4);
+        assert_eq!(game.players[2].hand.len(), 3);
+        assert_eq!(game.players[2].discard.len(), 2);
+        // The curse pile is limited, and shown to every player.
+        assert_eq!(game.curses, 7);
+        assert_eq!(PlayerView::new(&game, 1, &[]).curses, 7);
+        game.curses = 1;
+        game.players[2].resigned = false;
+        game.players[0].hand = vec![Card {
+            action: Some(CardAction::Hex),
+            ..Default::default()
+        }];
+        game.process_action(&draw, rng).unwrap();
+        assert_eq!(game.curses, 0);
+        assert_eq!(game.players[1].discard.len(), 5);
+        assert_eq!(game.players[2].discard.len(), 2);
+        // Curses can't be played.
+        game.players[0].hand = vec![Card::curse()];
+        assert!(game.process_action(&draw, rng).is_err());
+    }
+
+    #[test]
+    fn transposition_hash() {
+        let rng = &mut rand::rng();
+        let game =
+            GameState::new(2, "easy1", &RuleSet::default(), rng).unwrap();
+        let mut a = game.clone();
+        a.process_action(&PlayerAction::Discard(vec![0, 1]), rng)
+            .unwrap();
+        let mut b = game.clone();
+        b.process_action(&PlayerAction::Discard(vec![1]), rng)
+            .unwrap();
+        b.process_action(&PlayerAction::Discard(vec![0]), rng)
+            .unwrap();
+        assert_ne!(a.hash(), game.hash());
+        assert_eq!(a.hash(), b.hash());
+        // Incremental updates match a full rehash.
+        a.process_action(&PlayerAction::FinishTurn, rng).unwrap();
+        let expected = a.hash();
+        a.rehash();
+        assert_eq!(a.hash(), expected);
+        // Moving a card between deck and discard pile, or changing the
+        // round, gives a different position.
+        let mut c = game.clone();
+        let card = c.players[0].deck.pop().unwrap();
+        c.players[0].discard.push(card);
+        c.rehash();
+        assert_ne!(c.hash(), game.hash());
+        let mut d = game.clone();
+        d.round_idx += 1;
+        d.rehash();
+        assert_ne!(d.hash(), game.hash());
+    }
+
+    #[test]
+    fn apply_and_undo() {
+        fn summary(game: &GameState) -> String {
+            let players: Vec<_> = game
+                .players
+                .iter()
+                .map(|p| {
+                    (
+                        p.position,
+                        [&p.hand, &p.played, &p.deck, &p.discard],
+                        (&p.tokens, &p.used_tokens, &p.visited_caves),
+                        (p.trashes, p.can_buy, p.broken_barriers.len()),
+                    )
+                })
+                .collect();
+            format!(
+                "{players:?} {} {} {:?} {:?} {} {} {} {}",
+                serde_json::to_string(&game.shop).unwrap(),
+                serde_json::to_string(&game.storage).unwrap(),
+                game.barriers,
+                game.bonuses,
+                game.curses,
+                game.curr_player_idx,
+                game.round_idx,
+                game.hash()
+            )
+        }
+        let rng = &mut rand::rng();
+        let players = vec!["a".into(), "b".into(), "c".into()];
+        let mut record =
+            GameRecord::new("easy1", 0, players, RuleSet::default());
+        record.expansions = vec![Expansion::HeroesAndHexes];
+        let (mut game, _) = record.start_game().unwrap();
+        let mut agent = crate::agent::create_agent(1);
+        let mut history = vec![];
+        for _ in 0..150 {
+            let before = summary(&game);
+            let action = agent.choose_action(&game, rng);
+            let (outcome, undo) = game.apply(&action, rng).unwrap();
+            // The incrementally updated hash matches a full rehash.
+            let mut rehashed = game.clone();
+            rehashed.rehash();
+            assert_eq!(game.hash(), rehashed.hash());
+            history.push((before, undo));
+            if matches!(outcome, ActionOutcome::GameOver) {
+                break;
+            }
+        }
+        while let Some((before, undo)) = history.pop() {
+            game.undo(undo);
+            assert_eq!(summary(&game), before);
+        }
+    }
 }
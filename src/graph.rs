@@ -80,6 +80,25 @@ impl HexGraph {
             &self.adj,
             start_board_idx,
             map.finish_idx,
+            &[],
+            cost_fn,
+        )
+    }
+    /// Like `distances_to_finish`, but paths may not enter the `blocked`
+    /// node indices (e.g. hexes occupied by other players).
+    pub fn distances_avoiding(
+        &self,
+        map: &HexMap,
+        start_board_idx: u8,
+        blocked: &[usize],
+        cost_fn: impl Fn(&Node) -> f64,
+    ) -> Vec<f64> {
+        custom_distances(
+            map,
+            &self.adj,
+            start_board_idx,
+            map.finish_idx,
+            blocked,
             cost_fn,
         )
     }
@@ -140,6 +159,7 @@ fn custom_distances(
     adj: &[[usize; 6]],
     start_board_idx: u8,
     finish_board_idx: u8,
+    blocked: &[usize],
     cost_fn: impl Fn(&Node) -> f64,
 ) -> Vec<f64> {
     // Min-heap element.
@@ -173,7 +193,9 @@ fn custom_distances(
         }
     }
     while let Some(MinElem { cost, idx }) = queue.pop() {
-        if cost > dists[idx] {
+        // Searching backwards, so nothing can reach a blocked node's
+        // neighbors by way of it.
+        if cost > dists[idx] || blocked.contains(&idx) {
             continue;
         }
         let node = map.node_at_idx(idx).unwrap();
@@ -1,7 +1,18 @@
 pub mod agent;
 pub mod api;
-mod cards;
+pub mod cards;
+pub mod catalog;
 pub mod data;
+pub mod dataset;
+pub mod features;
 pub mod game;
 pub mod graph;
+pub mod notation;
 mod player;
+pub mod record;
+pub mod render;
+pub mod rules;
+pub mod tournament;
+pub mod tuning;
+pub mod view;
+mod zobrist;
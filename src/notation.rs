@@ -0,0 +1,226 @@
+//! A compact text notation for player actions, for typing and logging:
+//!
+//! ```text
+//! move c0 E,E,NE       move along a path using hand card 0
+//! move c0,c1 t2 W      several cards and/or tokens at once
+//! move NE              no cards, e.g. to enter a cave
+//! buy shop2 c0,c1,c3   buy shop card 2, paying with hand cards 0, 1 and 3
+//! buy storage0 c1 t0   buy from storage, paying with a card and a token
+//! draw c1              play hand card 1's draw action
+//! draw t1              use token 1 to draw
+//! trash c0,c2          trash hand cards
+//! discard c2           discard hand cards
+//! end                  finish the turn
+//! ```
+//!
+//! Hand cards are `c<index>` and tokens `t<index>`, separated by commas or
+//! spaces. Directions are NE, E, SE, SW, W or NW. Case is ignored.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::data::HexDirection;
+use crate::game::{
+    BuyCardAction, BuyIndex, DrawAction, MoveAction, PlayerAction,
+};
+
+const ACTION_NAMES: &str = "move, buy, draw, trash, discard or end";
+
+impl fmt::Display for PlayerAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayerAction::Move(mv) => {
+                write!(f, "move{}", refs(&mv.cards, &mv.tokens))?;
+                let path: Vec<_> = mv.path.iter().map(|d| d.abbrev()).collect();
+                write!(f, " {}", path.join(","))
+            }
+            PlayerAction::BuyCard(buy) => {
+                let slot = match buy.index {
+                    BuyIndex::Shop(i) => format!("shop{i}"),
+                    BuyIndex::Storage(i) => format!("storage{i}"),
+                };
+                write!(f, "buy {slot}{}", refs(&buy.cards, &buy.tokens))
+            }
+            PlayerAction::Draw(draw) => {
+                let cards: Vec<_> = draw.card.into_iter().collect();
+                let tokens: Vec<_> = draw.token.into_iter().collect();
+                write!(f, "draw{}", refs(&cards, &tokens))
+            }
+            PlayerAction::Trash(cards) => {
+                write!(f, "trash{}", refs(cards, &[]))
+            }
+            PlayerAction::Discard(cards) => {
+                write!(f, "discard{}", refs(cards, &[]))
+            }
+            PlayerAction::FinishTurn => write!(f, "end"),
+        }
+    }
+}
+
+// Card and token lists, each with a leading space if not empty.
+fn refs(cards: &[usize], tokens: &[usize]) -> String {
+    let mut out = String::new();
+    for (prefix, idxs) in [("c", cards), ("t", tokens)] {
+        if !idxs.is_empty() {
+            let items: Vec<_> =
+                idxs.iter().map(|i| format!("{prefix}{i}")).collect();
+            out.push(' ');
+            out.push_str(&items.join(","));
+        }
+    }
+    out
+}
+
+impl FromStr for PlayerAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let Some((verb, args)) = words.split_first() else {
+            return Err(format!("Empty action; expected {ACTION_NAMES}"));
+        };
+        match verb.to_ascii_lowercase().as_str() {
+            "move" => {
+                let Some((path, args)) = args.split_last() else {
+                    return Err("move needs a path, like `move c0 E,NE`".into());
+                };
+                let (cards, tokens) = parse_refs(args)?;
+                Ok(PlayerAction::Move(MoveAction {
+                    cards,
+                    tokens,
+                    path: parse_path(path)?,
+                }))
+            }
+            "buy" => {
+                let Some((slot, args)) = args.split_first() else {
+                    return Err("buy needs a slot, like `buy shop2 c0`".into());
+                };
+                let (cards, tokens) = parse_refs(args)?;
+                Ok(PlayerAction::BuyCard(BuyCardAction {
+                    cards,
+                    tokens,
+                    index: parse_slot(slot)?,
+                }))
+            }
+            "draw" => {
+                let (cards, tokens) = parse_refs(args)?;
+                if cards.len() > 1 || tokens.len() > 1 {
+                    return Err("draw takes at most one card and token".into());
+                }
+                Ok(PlayerAction::Draw(DrawAction {
+                    card: cards.first().copied(),
+                    token: tokens.first().copied(),
+                }))
+            }
+            "trash" => Ok(PlayerAction::Trash(parse_cards(verb, args)?)),
+            "discard" => Ok(PlayerAction::Discard(parse_cards(verb, args)?)),
+            "end" if args.is_empty() => Ok(PlayerAction::FinishTurn),
+            "end" => Err("end takes no arguments".into()),
+            _ => {
+                Err(format!("Unknown action {verb:?}; expected {ACTION_NAMES}"))
+            }
+        }
+    }
+}
+
+// Parse card (c0) and token (t1) references, in any order.
+fn parse_refs(args: &[&str]) -> Result<(Vec<usize>, Vec<usize>), String> {
+    let mut cards = vec![];
+    let mut tokens = vec![];
+    for item in args.iter().flat_map(|a| a.split(',')) {
+        if item.is_empty() {
+            continue;
+        }
+        let mut chars = item.chars();
+        let list = match chars.next() {
+            Some('c' | 'C') => &mut cards,
+            Some('t' | 'T') => &mut tokens,
+            _ => return Err(bad_ref(item)),
+        };
+        list.push(chars.as_str().parse().map_err(|_| bad_ref(item))?);
+    }
+    Ok((cards, tokens))
+}
+
+fn bad_ref(item: &str) -> String {
+    format!("Expected a card (like c0) or token (like t1), got {item:?}")
+}
+
+fn parse_cards(verb: &str, args: &[&str]) -> Result<Vec<usize>, String> {
+    let (cards, tokens) = parse_refs(args)?;
+    if !tokens.is_empty() {
+        return Err(format!("{verb} only takes cards, not tokens"));
+    }
+    Ok(cards)
+}
+
+fn parse_path(path: &str) -> Result<Vec<HexDirection>, String> {
+    path.split(',')
+        .filter(|d| !d.is_empty())
+        .map(|d| {
+            HexDirection::from_abbrev(d).ok_or_else(|| {
+                format!(
+                    "Invalid direction {d:?}; expected NE, E, SE, SW, W or NW"
+                )
+            })
+        })
+        .collect()
+}
+
+fn parse_slot(slot: &str) -> Result<BuyIndex, String> {
+    let lower = slot.to_ascii_lowercase();
+    let index = if let Some(num) = lower.strip_prefix("storage") {
+        num.parse().ok().map(BuyIndex::Storage)
+    } else if let Some(num) = lower.strip_prefix("shop") {
+        num.parse().ok().map(BuyIndex::Shop)
+    } else {
+        None
+    };
+    index.ok_or_else(|| {
+        format!(
+            "Expected a shop or storage slot (like shop2 or storage0), got {slot:?}"
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let actions = [
+            "move c0 E,E,NE",
+            "move c0,c1 t2 W",
+            "move NE",
+            "buy shop2 c0,c1,c3",
+            "buy storage0 c1 t0",
+            "draw c1",
+            "draw t1",
+            "trash c0,c2",
+            "discard c2",
+            "discard",
+            "end",
+        ];
+        for text in actions {
+            let action: PlayerAction = text.parse().unwrap();
+            assert_eq!(action.to_string(), text);
+        }
+        // Spacing, separators and case are flexible.
+        let action: PlayerAction = "MOVE c0 , t1  se,sw".parse().unwrap();
+        assert_eq!(action.to_string(), "move c0 t1 SE,SW");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = |s: &str| s.parse::<PlayerAction>().unwrap_err();
+        assert!(err("").contains("Empty action"));
+        assert!(err("mvoe c0 E").contains("Unknown action \"mvoe\""));
+        assert!(err("move c0 N").contains("Invalid direction \"N\""));
+        assert!(err("move").contains("needs a path"));
+        assert!(err("buy c0").contains("shop or storage slot"));
+        assert!(err("trash t0").contains("only takes cards"));
+        assert!(err("discard x1").contains("like c0"));
+        assert!(err("end now").contains("no arguments"));
+    }
+}
@@ -1,23 +1,60 @@
 use crate::cards::Card;
 use crate::data::{AxialCoord, BonusToken, BrokenBarrier};
+use crate::rules::RuleSet;
+use crate::zobrist::ZobristHash;
 use rand::prelude::SliceRandom;
 
-const HAND_SIZE: usize = 4;
+// Piles of cards and tokens, keeping equal items in different places apart
+// in the piles' hash.
+const HAND: u8 = 0;
+const PLAYED: u8 = 1;
+const DECK: u8 = 2;
+const DISCARD: u8 = 3;
+const TOKENS: u8 = 4;
+const VISITED_CAVES: u8 = 5;
+const USED_TOKENS: u8 = 6;
 
 #[derive(Clone)]
 pub struct Player {
     pub position: AxialCoord,
-    deck: Vec<Card>,
+    pub(crate) deck: Vec<Card>,
     pub hand: Vec<Card>,
     pub(crate) played: Vec<Card>,
     pub(crate) discard: Vec<Card>,
     pub tokens: Vec<BonusToken>,
+    // Tokens already spent. They never go back into the caves.
+    pub used_tokens: Vec<BonusToken>,
     pub trashes: usize,
     pub can_buy: bool,
     // Cave positions added when visited, removed when the player moves away.
     pub visited_caves: Vec<AxialCoord>,
     // Barriers broken, used for tie-breaking.
     pub broken_barriers: Vec<BrokenBarrier>,
+    // Resigned players no longer take turns.
+    pub resigned: bool,
+    // Hash of the piles above, updated as cards and tokens move.
+    piles_hash: ZobristHash,
+}
+
+pub(crate) fn count_cards(mut cards: Vec<&Card>) -> Vec<(&Card, usize)> {
+    cards.sort_unstable();
+    if cards.len() < 2 {
+        return cards.into_iter().map(|c| (c, 1)).collect();
+    }
+    let mut prev_card = cards[0];
+    let mut count = 1;
+    let mut result = Vec::new();
+    for &card in &cards[1..] {
+        if card == prev_card {
+            count += 1;
+        } else {
+            result.push((prev_card, count));
+            prev_card = card;
+            count = 1;
+        }
+    }
+    result.push((prev_card, count));
+    result
 }
 
 fn rev_sorted(xs: &[usize]) -> Vec<usize> {
@@ -26,23 +63,44 @@ fn rev_sorted(xs: &[usize]) -> Vec<usize> {
     result
 }
 
+// Undo moving the items at `idxs` of `from` (by swap_remove, in reverse
+// sorted order) onto the end of `to`.
+fn unmove<T>(from: &mut Vec<T>, idxs: &[usize], to: &mut Vec<T>) {
+    for i in rev_sorted(idxs).into_iter().rev() {
+        from.push(to.pop().unwrap());
+        let last = from.len() - 1;
+        from.swap(i, last);
+    }
+}
+
+/// How `Player::fill_hand` drew its cards, so it can be undone.
+#[derive(Clone, Default)]
+pub(crate) struct Refill {
+    // Cards drawn before and after the discard pile was shuffled in.
+    before_shuffle: usize,
+    after_shuffle: usize,
+    // The discard pile that was shuffled into the deck, if any.
+    shuffled: Option<Vec<Card>>,
+}
+
+/// What `Player::finish_turn` changed, so it can be undone.
+#[derive(Clone)]
+pub(crate) struct TurnEnd {
+    num_played: usize,
+    refill: Refill,
+    trashes: usize,
+    can_buy: bool,
+}
+
 impl Player {
     pub(crate) fn new(
         position: AxialCoord,
+        rules: &RuleSet,
         rng: &mut dyn rand::RngCore,
     ) -> Self {
-        let mut deck = vec![
-            Card::explorer(),
-            Card::explorer(),
-            Card::explorer(),
-            Card::traveler(),
-            Card::traveler(),
-            Card::traveler(),
-            Card::traveler(),
-            Card::sailor(),
-        ];
+        let mut deck = rules.starting_deck.clone();
         deck.shuffle(rng);
-        let hand = deck.split_off(HAND_SIZE);
+        let hand = deck.split_off(deck.len().saturating_sub(rules.hand_size));
         Self {
             position,
             deck,
@@ -50,11 +108,15 @@ impl Player {
             played: Vec::new(),
             discard: Vec::new(),
             tokens: Vec::new(),
+            used_tokens: Vec::new(),
             trashes: 0,
             can_buy: true,
             visited_caves: Vec::new(),
             broken_barriers: Vec::new(),
+            resigned: false,
+            piles_hash: ZobristHash::default(),
         }
+        .rehashed()
     }
     pub(crate) fn from_parts(
         position: AxialCoord,
@@ -68,37 +130,220 @@ impl Player {
             played: Vec::new(),
             discard: Vec::new(),
             tokens,
+            used_tokens: Vec::new(),
             trashes: 0,
             can_buy: false,
             visited_caves: Vec::new(),
             broken_barriers,
+            resigned: false,
+            piles_hash: ZobristHash::default(),
+        }
+        .rehashed()
+    }
+    /// Rebuild a player from their card piles, e.g. as seen in a PlayerView.
+    pub(crate) fn from_piles(
+        position: AxialCoord,
+        deck: Vec<Card>,
+        hand: Vec<Card>,
+        played: Vec<Card>,
+        discard: Vec<Card>,
+    ) -> Self {
+        Self {
+            deck,
+            hand,
+            played,
+            discard,
+            can_buy: true,
+            ..Self::from_parts(position, vec![], vec![])
         }
+        .rehashed()
+    }
+    fn rehashed(mut self) -> Self {
+        self.rehash();
+        self
+    }
+    /// Recompute the piles' hash, after changing them directly.
+    pub(crate) fn rehash(&mut self) {
+        let mut hash = ZobristHash::default();
+        for (pile, cards) in [
+            (HAND, &self.hand),
+            (PLAYED, &self.played),
+            (DECK, &self.deck),
+            (DISCARD, &self.discard),
+        ] {
+            for card in cards {
+                hash.add(&(pile, card));
+            }
+        }
+        for (pile, tokens) in
+            [(TOKENS, &self.tokens), (USED_TOKENS, &self.used_tokens)]
+        {
+            for token in tokens {
+                hash.add(&(pile, token));
+            }
+        }
+        for cave in &self.visited_caves {
+            hash.add(&(VISITED_CAVES, cave));
+        }
+        self.piles_hash = hash;
+    }
+    /// Order-independent hash of the cards and tokens in each pile. The deck
+    /// and discard pile are kept apart, since discarded cards only come back
+    /// after a reshuffle, but the deck's order is ignored.
+    pub(crate) fn piles_hash(&self) -> u64 {
+        self.piles_hash.value()
     }
     /// Move specified `cards` from self.hand into self.played.
     pub(crate) fn mark_played(&mut self, cards: &[usize]) {
         for i in rev_sorted(cards) {
-            self.played.push(self.hand.swap_remove(i));
+            let card = self.hand.swap_remove(i);
+            self.piles_hash.shift(&card, HAND, PLAYED);
+            self.played.push(card);
+        }
+    }
+    /// Reverse a call to `mark_played`.
+    pub(crate) fn unmark_played(&mut self, cards: &[usize]) {
+        unmove(&mut self.hand, cards, &mut self.played);
+        for &i in cards {
+            self.piles_hash.shift(&self.hand[i], PLAYED, HAND);
         }
     }
     /// Move specified `cards` from self.hand directly into self.discard.
     pub(crate) fn discard_cards(&mut self, cards: &[usize]) {
         for i in rev_sorted(cards) {
-            self.discard.push(self.hand.swap_remove(i));
+            let card = self.hand.swap_remove(i);
+            self.piles_hash.shift(&card, HAND, DISCARD);
+            self.discard.push(card);
+        }
+    }
+    /// Reverse a call to `discard_cards`.
+    pub(crate) fn undiscard_cards(&mut self, cards: &[usize]) {
+        unmove(&mut self.hand, cards, &mut self.discard);
+        for &i in cards {
+            self.piles_hash.shift(&self.hand[i], DISCARD, HAND);
         }
     }
-    /// Remove specified `cards` from self.hand permanently.
-    pub(crate) fn trash_cards(&mut self, cards: &[usize]) {
+    /// Remove specified `cards` from self.hand permanently, returning them.
+    pub(crate) fn trash_cards(&mut self, cards: &[usize]) -> Vec<Card> {
+        let mut trashed = Vec::with_capacity(cards.len());
         for i in rev_sorted(cards) {
-            self.hand.swap_remove(i);
+            let card = self.hand.swap_remove(i);
+            self.piles_hash.remove(&(HAND, &card));
+            trashed.push(card);
         }
+        trashed
     }
-    /// Remove specified `tokens` from self.tokens permanently.
-    pub(crate) fn trash_tokens(&mut self, tokens: &[usize]) {
-        if tokens.is_empty() {
-            return;
+    /// Reverse a call to `trash_cards`.
+    pub(crate) fn untrash_cards(
+        &mut self,
+        cards: &[usize],
+        mut trashed: Vec<Card>,
+    ) {
+        unmove(&mut self.hand, cards, &mut trashed);
+        for &i in cards {
+            self.piles_hash.add(&(HAND, &self.hand[i]));
+        }
+    }
+    /// Replace the hand with the cards at `played`, set aside into
+    /// self.played, returning the old hand.
+    pub(crate) fn spend_hand(&mut self, played: &[usize]) -> Vec<Card> {
+        for &i in played {
+            let card = self.hand[i].clone();
+            self.piles_hash.add(&(PLAYED, &card));
+            self.played.push(card);
+        }
+        for card in &self.hand {
+            self.piles_hash.remove(&(HAND, card));
+        }
+        std::mem::take(&mut self.hand)
+    }
+    /// Reverse a call to `spend_hand`.
+    pub(crate) fn unspend_hand(&mut self, hand: Vec<Card>, num_played: usize) {
+        for card in self.played.drain(self.played.len() - num_played..) {
+            self.piles_hash.remove(&(PLAYED, &card));
+        }
+        for card in &hand {
+            self.piles_hash.add(&(HAND, card));
+        }
+        self.hand = hand;
+    }
+    /// Add a new card to self.discard.
+    pub(crate) fn gain_card(&mut self, card: Card) {
+        self.piles_hash.add(&(DISCARD, &card));
+        self.discard.push(card);
+    }
+    /// Reverse a call to `gain_card`.
+    pub(crate) fn ungain_card(&mut self) {
+        if let Some(card) = self.discard.pop() {
+            self.piles_hash.remove(&(DISCARD, &card));
+        }
+    }
+    /// Add a new token to self.tokens.
+    pub(crate) fn gain_token(&mut self, token: BonusToken) {
+        self.piles_hash.add(&(TOKENS, &token));
+        self.tokens.push(token);
+    }
+    /// Reverse a call to `gain_token`, returning the token.
+    pub(crate) fn ungain_token(&mut self) -> Option<BonusToken> {
+        let token = self.tokens.pop()?;
+        self.piles_hash.remove(&(TOKENS, &token));
+        Some(token)
+    }
+    /// Take `token` from the cave at `pos`.
+    pub(crate) fn visit_cave(&mut self, pos: AxialCoord, token: BonusToken) {
+        self.gain_token(token);
+        self.piles_hash.add(&(VISITED_CAVES, &pos));
+        self.visited_caves.push(pos);
+    }
+    /// Reverse a call to `visit_cave`, returning the token.
+    pub(crate) fn unvisit_cave(&mut self) -> Option<BonusToken> {
+        if let Some(pos) = self.visited_caves.pop() {
+            self.piles_hash.remove(&(VISITED_CAVES, &pos));
+        }
+        self.ungain_token()
+    }
+    /// Move to `pos`, forgetting visited caves that are no longer adjacent.
+    /// Returns the previous position and visited caves.
+    pub(crate) fn move_to(
+        &mut self,
+        pos: AxialCoord,
+    ) -> (AxialCoord, Vec<AxialCoord>) {
+        let visited_caves = self.visited_caves.clone();
+        for cave in &visited_caves {
+            if !pos.is_adjacent(*cave) {
+                self.piles_hash.remove(&(VISITED_CAVES, cave));
+            }
+        }
+        self.visited_caves.retain(|&cave| pos.is_adjacent(cave));
+        (std::mem::replace(&mut self.position, pos), visited_caves)
+    }
+    /// Reverse a call to `move_to`.
+    pub(crate) fn unmove_to(
+        &mut self,
+        from: AxialCoord,
+        visited_caves: Vec<AxialCoord>,
+    ) {
+        for cave in &visited_caves {
+            if !self.position.is_adjacent(*cave) {
+                self.piles_hash.add(&(VISITED_CAVES, cave));
+            }
         }
+        self.position = from;
+        self.visited_caves = visited_caves;
+    }
+    /// Move specified `tokens` from self.tokens into self.used_tokens.
+    pub(crate) fn trash_tokens(&mut self, tokens: &[usize]) {
         for i in rev_sorted(tokens) {
-            self.tokens.swap_remove(i);
+            let token = self.tokens.swap_remove(i);
+            self.piles_hash.shift(&token, TOKENS, USED_TOKENS);
+            self.used_tokens.push(token);
+        }
+    }
+    /// Reverse a call to `trash_tokens`.
+    pub(crate) fn untrash_tokens(&mut self, tokens: &[usize]) {
+        unmove(&mut self.tokens, tokens, &mut self.used_tokens);
+        for &i in tokens {
+            self.piles_hash.shift(&self.tokens[i], USED_TOKENS, TOKENS);
         }
     }
     /// Fill hand from the deck, adding shuffled cards from the discard if needed.
@@ -106,35 +351,137 @@ impl Player {
         &mut self,
         hand_size: usize,
         rng: &mut dyn rand::RngCore,
-    ) {
+    ) -> Refill {
+        let mut refill = Refill::default();
         while self.hand.len() < hand_size {
             if self.deck.is_empty() && !self.discard.is_empty() {
-                // Shuffle the discard pile into the deck.
+                // Shuffle the discard pile into the deck. This empties the
+                // discard pile, so it happens at most once.
+                for card in &self.discard {
+                    self.piles_hash.shift(card, DISCARD, DECK);
+                }
+                refill.shuffled = Some(self.discard.clone());
                 self.deck.append(&mut self.discard);
                 self.deck.shuffle(rng);
             }
             if let Some(card) = self.deck.pop() {
+                self.piles_hash.shift(&card, DECK, HAND);
                 self.hand.push(card);
+                if refill.shuffled.is_some() {
+                    refill.after_shuffle += 1;
+                } else {
+                    refill.before_shuffle += 1;
+                }
             } else {
                 break;
             }
         }
+        refill
     }
-    /// Set aside current hand into played, and draw a new hand.
-    pub(crate) fn replace_hand(&mut self, rng: &mut dyn rand::RngCore) {
+    /// Reverse a call to `fill_hand`.
+    pub(crate) fn unfill_hand(&mut self, refill: Refill) {
+        for _ in 0..refill.after_shuffle {
+            self.undraw_card();
+        }
+        if let Some(discard) = refill.shuffled {
+            // The deck was empty before the shuffle.
+            for card in &discard {
+                self.piles_hash.shift(card, DECK, DISCARD);
+            }
+            self.deck.clear();
+            self.discard = discard;
+        }
+        for _ in 0..refill.before_shuffle {
+            self.undraw_card();
+        }
+    }
+    fn undraw_card(&mut self) {
+        if let Some(card) = self.hand.pop() {
+            self.piles_hash.shift(&card, HAND, DECK);
+            self.deck.push(card);
+        }
+    }
+    /// Shuffle the deck, whose order is hidden from all players.
+    pub(crate) fn shuffle_deck(&mut self, rng: &mut dyn rand::RngCore) {
+        self.deck.shuffle(rng);
+    }
+    /// Re-deal the hand from the combined hand and deck, keeping its size.
+    pub(crate) fn redeal_hand(&mut self, rng: &mut dyn rand::RngCore) {
+        let hand_size = self.hand.len();
+        self.move_pile(HAND, DECK);
+        self.deck.shuffle(rng);
+        self.hand = self.deck.split_off(self.deck.len() - hand_size);
+        for card in &self.hand {
+            self.piles_hash.shift(card, DECK, HAND);
+        }
+    }
+    /// Set aside current hand into played, and draw a new hand. Returns the
+    /// number of cards set aside.
+    pub(crate) fn replace_hand(
+        &mut self,
+        rng: &mut dyn rand::RngCore,
+    ) -> (usize, Refill) {
         let num_current = self.hand.len();
-        self.played.append(&mut self.hand);
-        self.fill_hand(num_current, rng);
+        self.move_pile(HAND, PLAYED);
+        (num_current, self.fill_hand(num_current, rng))
+    }
+    /// Reverse a call to `replace_hand`.
+    pub(crate) fn unreplace_hand(&mut self, num_cards: usize, refill: Refill) {
+        self.unfill_hand(refill);
+        self.hand = self.played.split_off(self.played.len() - num_cards);
+        for card in &self.hand {
+            self.piles_hash.shift(card, PLAYED, HAND);
+        }
     }
     /// Clean up after the turn is over.
-    pub(crate) fn finish_turn(&mut self, rng: &mut dyn rand::RngCore) {
+    pub(crate) fn finish_turn(
+        &mut self,
+        hand_size: usize,
+        rng: &mut dyn rand::RngCore,
+    ) -> TurnEnd {
+        let num_played = self.played.len();
+        let (trashes, can_buy) = (self.trashes, self.can_buy);
         // Discard all played cards.
-        self.discard.append(&mut self.played);
+        self.move_pile(PLAYED, DISCARD);
         // Refill the hand for the next turn.
-        self.fill_hand(HAND_SIZE, rng);
+        let refill = self.fill_hand(hand_size, rng);
         // Reset per-turn state.
         self.trashes = 0;
         self.can_buy = true;
+        TurnEnd {
+            num_played,
+            refill,
+            trashes,
+            can_buy,
+        }
+    }
+    /// Reverse a call to `finish_turn`.
+    pub(crate) fn unfinish_turn(&mut self, end: TurnEnd) {
+        self.unfill_hand(end.refill);
+        self.played =
+            self.discard.split_off(self.discard.len() - end.num_played);
+        for card in &self.played {
+            self.piles_hash.shift(card, DISCARD, PLAYED);
+        }
+        self.trashes = end.trashes;
+        self.can_buy = end.can_buy;
+    }
+    // Append all of one card pile onto another.
+    fn move_pile(&mut self, from: u8, to: u8) {
+        let mut cards = std::mem::take(self.pile_mut(from));
+        for card in &cards {
+            self.piles_hash.shift(card, from, to);
+        }
+        self.pile_mut(to).append(&mut cards);
+    }
+    fn pile_mut(&mut self, pile: u8) -> &mut Vec<Card> {
+        match pile {
+            HAND => &mut self.hand,
+            PLAYED => &mut self.played,
+            DECK => &mut self.deck,
+            DISCARD => &mut self.discard,
+            _ => unreachable!("Not a card pile: {pile}"),
+        }
     }
 
     /// Total cards belonging to the player.
@@ -170,25 +517,13 @@ impl Player {
 
     // All unique cards belonging to the player, along with their counts.
     pub fn all_cards(&self) -> Vec<(&Card, usize)> {
-        let mut cards = self.cards_iter().collect::<Vec<_>>();
-        cards.sort_unstable();
-        if cards.len() < 2 {
-            return cards.into_iter().map(|c| (c, 1)).collect();
-        }
-        let mut prev_card = cards[0];
-        let mut count = 1;
-        let mut result = Vec::new();
-        for &card in &cards[1..] {
-            if card == prev_card {
-                count += 1;
-            } else {
-                result.push((prev_card, count));
-                prev_card = card;
-                count = 1;
-            }
-        }
-        result.push((prev_card, count));
-        result
+        count_cards(self.cards_iter().collect())
+    }
+
+    // Unique cards in the deck, along with their counts. The deck's order is
+    // hidden, but its contents can be tracked by watching the game.
+    pub fn deck_cards(&self) -> Vec<(&Card, usize)> {
+        count_cards(self.deck.iter().collect())
     }
 
     pub fn debug_str(&self, idx: usize) -> String {
@@ -214,9 +549,11 @@ mod tests {
 
     #[test]
     fn initialization() {
-        let p = Player::new(AxialCoord { q: 3, r: -2 }, &mut rand::rng());
+        let rules = RuleSet::default();
+        let p =
+            Player::new(AxialCoord { q: 3, r: -2 }, &rules, &mut rand::rng());
         assert_eq!(p.position, AxialCoord { q: 3, r: -2 });
-        assert_eq!(p.hand.len(), HAND_SIZE);
+        assert_eq!(p.hand.len(), rules.hand_size);
         assert_eq!(p.deck.len(), 4);
         assert_eq!(p.played.len(), 0);
         assert_eq!(p.discard.len(), 0);
@@ -0,0 +1,453 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::fmt;
+
+use crate::agent::AgentSpec;
+use crate::cards::Expansion;
+use crate::catalog::{
+    CatalogEntry, MarketSetup, load_catalog, validate_catalog,
+};
+use crate::game::{ActionOutcome, GameState, PlayerAction};
+use crate::rules::RuleSet;
+
+/// A complete, replayable record of a game, in a PGN-like text format:
+/// headers with the setup, then one line per run of actions by a player.
+///
+/// ```text
+/// [Layout "easy1"]
+/// [Seed 42]
+/// [Players ["alice", "bot"]]
+/// [Agents [null, {"kind": "greedy"}]]
+/// [Date "2026-01-31"]
+/// [Result "P1"]
+///
+/// 1.0 move c0 E,E; buy shop2 c1,c2; end
+/// 1.1 move c3 NE; discard c0,c1; end
+/// ```
+///
+/// Header values are JSON. Each line starts with the (1-based) round and the
+/// acting player, followed by actions in `notation` separated by `;`.
+/// All randomness during play comes from an RNG seeded with `seed`, so
+/// replaying the actions reproduces the game exactly.
+#[derive(Clone)]
+pub struct GameRecord {
+    pub layout: String,
+    pub seed: u64,
+    pub players: Vec<String>,
+    // The agent playing each seat at the start, or None for humans.
+    pub agents: Vec<Option<AgentSpec>>,
+    pub rules: RuleSet,
+    pub expansions: Vec<Expansion>,
+    pub market: MarketSetup,
+    // Custom card catalog, replacing the base game's cards.
+    pub catalog: Option<Vec<CatalogEntry>>,
+    // As YYYY-MM-DD.
+    pub date: String,
+    // "P<idx>" for the winner, "draw", or "*" if the game isn't over.
+    pub result: String,
+    pub turns: Vec<Turn>,
+}
+
+/// Consecutive actions by one player, within a round.
+#[derive(Clone, Debug)]
+pub struct Turn {
+    // 1-based round number.
+    pub round: usize,
+    pub player_idx: usize,
+    pub actions: Vec<RecordedAction>,
+    // Line number in the parsed text, or 0 if not parsed.
+    pub line: usize,
+}
+
+#[derive(Clone, Debug)]
+pub enum RecordedAction {
+    Action(PlayerAction),
+    // The player left the game, possibly outside their turn.
+    Resign,
+}
+
+impl fmt::Display for RecordedAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordedAction::Action(action) => write!(f, "{action}"),
+            RecordedAction::Resign => write!(f, "resign"),
+        }
+    }
+}
+
+impl GameRecord {
+    /// A record for a new game with the standard market and no agents.
+    pub fn new(
+        layout: &str,
+        seed: u64,
+        players: Vec<String>,
+        rules: RuleSet,
+    ) -> Self {
+        Self {
+            layout: layout.to_string(),
+            seed,
+            agents: vec![None; players.len()],
+            players,
+            rules,
+            expansions: vec![],
+            market: MarketSetup::Standard,
+            catalog: None,
+            date: today(),
+            result: "*".into(),
+            turns: vec![],
+        }
+    }
+
+    /// Create the starting game state, and the RNG to use for every action
+    /// so the game can be replayed.
+    pub fn start_game(&self) -> Result<(GameState, StdRng), Box<dyn Error>> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut state = GameState::new(
+            self.players.len(),
+            &self.layout,
+            &self.rules,
+            &mut rng,
+        )?;
+        let mut entries = match &self.catalog {
+            Some(entries) => {
+                validate_catalog(entries)?;
+                entries.clone()
+            }
+            None => load_catalog("base")?,
+        };
+        for (i, expansion) in self.expansions.iter().enumerate() {
+            if self.expansions[..i].contains(expansion) {
+                return Err(format!("Duplicate expansion {expansion:?}").into());
+            }
+            entries.extend(load_catalog(expansion.catalog_name())?);
+        }
+        let market =
+            self.market
+                .build(&entries, self.rules.shop_size, &mut rng)?;
+        state.set_market(market)?;
+        Ok((state, rng))
+    }
+
+    /// Record an action that was processed successfully, given the round
+    /// and acting player from before it was processed.
+    pub fn add(
+        &mut self,
+        round_idx: usize,
+        player_idx: usize,
+        action: RecordedAction,
+    ) {
+        let round = round_idx + 1;
+        match self.turns.last_mut() {
+            Some(turn)
+                if turn.round == round && turn.player_idx == player_idx =>
+            {
+                turn.actions.push(action);
+            }
+            _ => self.turns.push(Turn {
+                round,
+                player_idx,
+                actions: vec![action],
+                line: 0,
+            }),
+        }
+    }
+
+    /// Set the result from a finished (or abandoned) game.
+    pub fn set_result(&mut self, game: &GameState) {
+        self.result = if game.end_reason.is_none() {
+            "*".into()
+        } else {
+            let scores = game.player_scores();
+            let best = scores.iter().max().copied().unwrap_or(0);
+            let winners: Vec<_> =
+                (0..scores.len()).filter(|&i| scores[i] == best).collect();
+            match winners[..] {
+                [winner] => format!("P{winner}"),
+                _ => "draw".into(),
+            }
+        };
+    }
+
+    /// Parse a record, without checking that its actions are legal.
+    /// Errors include the line number.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut record = Self::new("", 0, vec![], RuleSet::default());
+        record.date = String::new();
+        let mut has = (false, false, false);
+        let mut agents = None;
+        for (i, line) in text.lines().enumerate() {
+            let line_num = i + 1;
+            let err = |e: String| format!("line {line_num}: {e}");
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.strip_suffix(']').ok_or_else(|| {
+                    err("Header is missing a closing ]".into())
+                })?;
+                let (name, value) =
+                    header.split_once(' ').ok_or_else(|| {
+                        err(format!("Header {header:?} has no value"))
+                    })?;
+                let value = value.trim();
+                match name {
+                    "Layout" => {
+                        record.layout = json(value).map_err(err)?;
+                        has.0 = true;
+                    }
+                    "Seed" => {
+                        record.seed = json(value).map_err(err)?;
+                        has.1 = true;
+                    }
+                    "Players" => {
+                        record.players = json(value).map_err(err)?;
+                        has.2 = true;
+                    }
+                    "Agents" => agents = Some(json(value).map_err(err)?),
+                    "Rules" => record.rules = json(value).map_err(err)?,
+                    "Expansions" => {
+                        record.expansions = json(value).map_err(err)?
+                    }
+                    "Market" => record.market = json(value).map_err(err)?,
+                    "Catalog" => {
+                        record.catalog = Some(json(value).map_err(err)?)
+                    }
+                    "Date" => record.date = json(value).map_err(err)?,
+                    "Result" => record.result = json(value).map_err(err)?,
+                    // Unknown headers are kept by other tools, so skip them.
+                    _ => {}
+                }
+                continue;
+            }
+            let mut turn = parse_turn(line).map_err(err)?;
+            turn.line = line_num;
+            record.turns.push(turn);
+        }
+        match has {
+            (false, _, _) => return Err("Missing [Layout] header".into()),
+            (_, false, _) => return Err("Missing [Seed] header".into()),
+            (_, _, false) => return Err("Missing [Players] header".into()),
+            _ => {}
+        }
+        record.agents =
+            agents.unwrap_or_else(|| vec![None; record.players.len()]);
+        if record.agents.len() != record.players.len() {
+            return Err("[Agents] and [Players] have different lengths".into());
+        }
+        Ok(record)
+    }
+
+    /// Replay the recorded actions from the start, returning the final
+    /// state. Illegal or out-of-order actions are rejected with their line.
+    pub fn replay(&self) -> Result<GameState, String> {
+        self.replay_with(|_, _, _, _| {})
+    }
+
+    /// Like `replay`, calling `on_action` after each action with the game,
+    /// the acting player, the action and its outcome.
+    pub fn replay_with(
+        &self,
+        mut on_action: impl FnMut(
+            &GameState,
+            usize,
+            &RecordedAction,
+            &ActionOutcome,
+        ),
+    ) -> Result<GameState, String> {
+        let (mut game, mut rng) =
+            self.start_game().map_err(|e| e.to_string())?;
+        for (i, turn) in self.turns.iter().enumerate() {
+            let at = match turn.line {
+                0 => format!("turn {}", i + 1),
+                line => format!("line {line}"),
+            };
+            if turn.round != game.round_idx + 1 {
+                return Err(format!(
+                    "{at}: expected round {}, got {}",
+                    game.round_idx + 1,
+                    turn.round
+                ));
+            }
+            for action in &turn.actions {
+                if game.end_reason.is_some() {
+                    return Err(format!("{at}: the game is already over"));
+                }
+                let result = match action {
+                    RecordedAction::Action(action) => {
+                        if turn.player_idx != game.curr_player_idx {
+                            return Err(format!(
+                                "{at}: it's P{}'s turn, not P{}'s",
+                                game.curr_player_idx, turn.player_idx
+                            ));
+                        }
+                        game.process_action(action, &mut rng)
+                    }
+                    RecordedAction::Resign => {
+                        game.resign(turn.player_idx, &mut rng)
+                    }
+                };
+                match result {
+                    Ok(outcome) => {
+                        on_action(&game, turn.player_idx, action, &outcome)
+                    }
+                    Err(e) => {
+                        return Err(format!(
+                            "{at}: `{action}` is illegal: {e}"
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(game)
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        header(f, "Layout", &self.layout)?;
+        header(f, "Seed", &self.seed)?;
+        header(f, "Players", &self.players)?;
+        header(f, "Agents", &self.agents)?;
+        header(f, "Rules", &self.rules)?;
+        if !self.expansions.is_empty() {
+            header(f, "Expansions", &self.expansions)?;
+        }
+        if !matches!(self.market, MarketSetup::Standard) {
+            header(f, "Market", &self.market)?;
+        }
+        if let Some(catalog) = &self.catalog {
+            header(f, "Catalog", catalog)?;
+        }
+        header(f, "Date", &self.date)?;
+        header(f, "Result", &self.result)?;
+        writeln!(f)?;
+        for turn in &self.turns {
+            let actions: Vec<_> =
+                turn.actions.iter().map(|a| a.to_string()).collect();
+            writeln!(
+                f,
+                "{}.{} {}",
+                turn.round,
+                turn.player_idx,
+                actions.join("; ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn header(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    value: &impl Serialize,
+) -> fmt::Result {
+    let json = serde_json::to_string(value).map_err(|_| fmt::Error)?;
+    writeln!(f, "[{name} {json}]")
+}
+
+fn json<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_str(value)
+        .map_err(|e| format!("Invalid value {value:?}: {e}"))
+}
+
+fn parse_turn(line: &str) -> Result<Turn, String> {
+    let (prefix, rest) =
+        line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let (round, player) = prefix
+        .split_once('.')
+        .and_then(|(r, p)| Some((r.parse().ok()?, p.parse().ok()?)))
+        .ok_or_else(|| {
+            format!("Expected a line like `1.0 move c0 E`, got {prefix:?}")
+        })?;
+    let actions = rest
+        .split(';')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|a| match a {
+            "resign" => Ok(RecordedAction::Resign),
+            _ => a.parse().map(RecordedAction::Action),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if actions.is_empty() {
+        return Err("Expected at least one action".into());
+    }
+    Ok(Turn {
+        round,
+        player_idx: player,
+        actions,
+        line: 0,
+    })
+}
+
+// Today's date (UTC) as YYYY-MM-DD.
+fn today() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    // Civil-from-days, from Howard Hinnant's date algorithms.
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::create_agent;
+
+    #[test]
+    fn round_trip_and_replay() {
+        let players = vec!["a".to_string(), "b".to_string()];
+        let mut record =
+            GameRecord::new("first", 7, players, RuleSet::default());
+        let (mut game, mut rng) = record.start_game().unwrap();
+        let mut agents = [create_agent(1), create_agent(1)];
+        // Agents get their own RNG, so they don't change the game's shuffles.
+        let agent_rng = &mut StdRng::seed_from_u64(1);
+        for _ in 0..60 {
+            let (round_idx, player_idx) =
+                (game.round_idx, game.curr_player_idx);
+            let action = agents[player_idx].choose_action(&game, agent_rng);
+            game.process_action(&action, &mut rng).unwrap();
+            record.add(round_idx, player_idx, RecordedAction::Action(action));
+            if game.end_reason.is_some() {
+                break;
+            }
+        }
+        record.set_result(&game);
+
+        let text = record.to_string();
+        let parsed = GameRecord::parse(&text).unwrap();
+        assert_eq!(parsed.to_string(), text);
+        let replayed = parsed.replay().unwrap();
+        assert_eq!(replayed.player_positions(), game.player_positions());
+        assert_eq!(replayed.round_idx, game.round_idx);
+
+        // An illegal action is reported with its line.
+        let bad = format!(
+            "{text}{}.{} buy shop99\n",
+            game.round_idx + 1,
+            game.curr_player_idx
+        );
+        let line = bad.lines().count();
+        let Err(err) = GameRecord::parse(&bad).unwrap().replay() else {
+            panic!("Replayed an illegal action");
+        };
+        assert!(err.starts_with(&format!("line {line}:")), "{err}");
+        let Err(err) = GameRecord::parse("[Seed 1]\n") else {
+            panic!("Parsed a record without a layout");
+        };
+        assert!(err.contains("Layout"), "{err}");
+    }
+}
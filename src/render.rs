@@ -0,0 +1,701 @@
+use std::fmt::Write;
+
+use crate::data::{ALL_DIRECTIONS, AxialCoord, Barrier, HexMap, Terrain};
+use crate::game::{ActionOutcome, GameState, PlayerAction};
+use crate::record::{GameRecord, RecordedAction};
+use crate::view::PlayerView;
+
+/// The parts of a game that are drawn, from a `GameState`, a `PlayerView`,
+/// or just a map.
+pub struct Scene<'a> {
+    pub map: &'a HexMap,
+    // Barriers still standing.
+    pub barriers: &'a [Barrier],
+    // (player index, position) of each player still in the game.
+    pub players: Vec<(usize, AxialCoord)>,
+    // Number of tokens left in each cave.
+    pub caves: Vec<(AxialCoord, usize)>,
+}
+
+impl<'a> Scene<'a> {
+    pub fn from_map(map: &'a HexMap) -> Self {
+        Self {
+            map,
+            barriers: &[],
+            players: vec![],
+            caves: vec![],
+        }
+    }
+
+    pub fn from_game(game: &'a GameState) -> Self {
+        Self {
+            map: &game.map,
+            barriers: &game.barriers,
+            players: game
+                .players
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| !p.resigned)
+                .map(|(i, p)| (i, p.position))
+                .collect(),
+            caves: game
+                .bonus_counts()
+                .into_iter()
+                .map(|(&pos, n)| (pos, n))
+                .collect(),
+        }
+    }
+
+    pub fn from_view(view: &'a PlayerView) -> Self {
+        let mut players: Vec<_> = view
+            .other_players
+            .iter()
+            .chain([&view.my_player.info])
+            .filter(|p| !p.resigned)
+            .map(|p| (p.player_idx, p.position))
+            .collect();
+        players.sort_by_key(|&(i, _)| i);
+        Self {
+            map: &view.map,
+            barriers: &view.barriers,
+            players,
+            caves: view.bonuses.clone(),
+        }
+    }
+}
+
+/// Settings for `render_svg`.
+#[derive(Clone, Debug)]
+pub struct SvgOptions {
+    // Distance from a hex's center to its corners, in SVG units.
+    pub hex_size: f32,
+    // If set, the image's width in pixels, e.g. for thumbnails. Otherwise
+    // it scales to fit its container.
+    pub width: Option<u32>,
+    // Label each hex with its cost (caves show their token count instead).
+    pub show_costs: bool,
+    // Custom labels by node index, replacing the costs.
+    pub labels: Option<Vec<String>>,
+    // Hexes to connect with a line, e.g. a planned move.
+    pub path: Vec<AxialCoord>,
+    // Hexes to highlight, e.g. those the current player can reach.
+    pub overlay: Vec<AxialCoord>,
+    // Text for the top left corner, e.g. the round.
+    pub caption: Option<String>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            hex_size: 30.0,
+            width: None,
+            show_costs: true,
+            labels: None,
+            path: vec![],
+            overlay: vec![],
+            caption: None,
+        }
+    }
+}
+
+/// Draw a game as a standalone SVG image.
+pub fn game_svg(game: &GameState, options: &SvgOptions) -> String {
+    render_svg(&Scene::from_game(game), options)
+}
+
+/// Draw a player's view of a game as a standalone SVG image.
+pub fn view_svg(view: &PlayerView, options: &SvgOptions) -> String {
+    render_svg(&Scene::from_view(view), options)
+}
+
+/// Draw a scene as a standalone SVG image: the hexes colored by terrain,
+/// board outlines, barriers colored by type, caves with their token counts,
+/// the players' pieces, and any path or overlay from `options`.
+pub fn render_svg(scene: &Scene, options: &SvgOptions) -> String {
+    let size = options.hex_size;
+    let mut out = String::new();
+    open_svg(scene.map, options, &mut out);
+    draw_board(scene, options, &mut out);
+    for (i, barrier) in scene.barriers.iter().enumerate() {
+        draw_barrier(i, barrier, size, "", &mut out);
+    }
+    if let Some(caption) = &options.caption {
+        draw_caption(scene.map, size, caption, "", "", &mut out);
+    }
+    if !options.path.is_empty() {
+        let points: Vec<_> = options
+            .path
+            .iter()
+            .map(|pos| {
+                let (cx, cy) = hex_center(*pos, size);
+                format!("{cx:.1},{cy:.1}")
+            })
+            .collect();
+        writeln!(
+            out,
+            "<polyline class=\"path\" points=\"{}\" fill=\"none\" stroke=\"#ff7f00\" stroke-width=\"{:.1}\" stroke-linecap=\"round\" stroke-linejoin=\"round\" opacity=\"0.8\" />",
+            points.join(" "),
+            size / 5.0
+        )
+        .unwrap();
+    }
+    for (player_idx, (cx, cy)) in piece_centers(&scene.players, size) {
+        draw_piece(player_idx, cx, cy, size, "", &mut out);
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn open_svg(map: &HexMap, options: &SvgOptions, out: &mut String) {
+    let (x, y, w, h) = view_box(map, options.hex_size);
+    let width = match options.width {
+        Some(px) => {
+            let px_height = (px as f32 * h / w).round();
+            format!(" width=\"{px}\" height=\"{px_height}\"")
+        }
+        None => String::new(),
+    };
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{x} {y} {w} {h}\"{width}>"
+    )
+    .unwrap();
+}
+
+// Where to draw each player's piece. Players sharing a hex are spread out
+// a little.
+fn piece_centers(
+    players: &[(usize, AxialCoord)],
+    size: f32,
+) -> Vec<(usize, (f32, f32))> {
+    players
+        .iter()
+        .enumerate()
+        .map(|(i, &(player_idx, pos))| {
+            let sharing = players[..i].iter().filter(|p| p.1 == pos).count();
+            let (cx, cy) = hex_center(pos, size);
+            let offset = sharing as f32 * size / 4.0;
+            (player_idx, (cx + offset, cy + offset))
+        })
+        .collect()
+}
+
+// The hexes, their labels, board outlines and the overlay: the parts that
+// never change.
+fn draw_board(scene: &Scene, options: &SvgOptions, out: &mut String) {
+    let size = options.hex_size;
+    let font_size = size / 2.0;
+    for (i, (pos, node)) in scene.map.all_nodes().enumerate() {
+        let (cx, cy) = hex_center(pos, size);
+        writeln!(
+            out,
+            "<polygon class=\"hex\" points=\"{}\" fill=\"{}\" stroke=\"#333\" stroke-width=\"1\" />",
+            hex_points(pos, size),
+            terrain_color(node.terrain)
+        )
+        .unwrap();
+        let cave = scene.caves.iter().find(|(p, _)| *p == pos);
+        let label = match (&options.labels, cave) {
+            (Some(labels), _) => labels.get(i).cloned().unwrap_or_default(),
+            (None, Some((_, count))) => count.to_string(),
+            (None, None) if !options.show_costs => String::new(),
+            (None, None) => match node.terrain {
+                Terrain::Invalid | Terrain::Cave => String::new(),
+                _ => node.cost.to_string(),
+            },
+        };
+        if !label.is_empty() {
+            let id = match cave {
+                Some(_) => format!(" id=\"cave-{}-{}\"", pos.q, pos.r),
+                None => String::new(),
+            };
+            writeln!(
+                out,
+                "<text{id} x=\"{cx:.1}\" y=\"{cy:.1}\" font-size=\"{font_size:.1}\" font-family=\"sans-serif\" dominant-baseline=\"middle\" text-anchor=\"middle\">{label}</text>"
+            )
+            .unwrap();
+        }
+    }
+    for pos in &options.overlay {
+        writeln!(
+            out,
+            "<polygon class=\"overlay\" points=\"{}\" fill=\"#ffffff\" opacity=\"0.45\" />",
+            hex_points(*pos, size)
+        )
+        .unwrap();
+    }
+    // Board outlines: edges facing another board or the outside.
+    for (pos, node) in scene.map.all_nodes() {
+        for (dir_idx, dir) in ALL_DIRECTIONS.iter().enumerate() {
+            let other = scene.map.node_at(dir.neighbor_coord(pos));
+            let is_outline = match other {
+                None => true,
+                Some(other) => node.board_idx < other.board_idx,
+            };
+            if is_outline {
+                draw_edge(pos, dir_idx, size, "#000", size / 10.0, out);
+            }
+        }
+    }
+}
+
+// A barrier's edges, colored by its terrain. `children` may animate it.
+fn draw_barrier(
+    id: usize,
+    barrier: &Barrier,
+    size: f32,
+    children: &str,
+    out: &mut String,
+) {
+    writeln!(out, "<g class=\"barrier\" id=\"barrier-{id}\">{children}")
+        .unwrap();
+    let color = terrain_color(barrier.terrain);
+    for &(pos, dir) in barrier.edges.iter() {
+        let dir_idx = ALL_DIRECTIONS.iter().position(|d| *d == dir).unwrap();
+        draw_edge(pos, dir_idx, size, color, size / 4.0, out);
+    }
+    out.push_str("</g>\n");
+}
+
+// `children` may animate the piece.
+fn draw_piece(
+    player_idx: usize,
+    cx: f32,
+    cy: f32,
+    size: f32,
+    children: &str,
+    out: &mut String,
+) {
+    writeln!(
+        out,
+        "<g class=\"piece\" id=\"player-{player_idx}\" transform=\"translate({cx:.1},{cy:.1})\">{children}<circle r=\"{:.1}\" fill=\"{}\" stroke=\"#000\" stroke-width=\"2\" /><text font-size=\"{:.1}\" font-family=\"sans-serif\" font-weight=\"bold\" fill=\"#fff\" dominant-baseline=\"middle\" text-anchor=\"middle\">{player_idx}</text></g>",
+        size / 2.5,
+        player_color(player_idx),
+        size / 2.2
+    )
+    .unwrap();
+}
+
+// Text in the top left corner. `attrs` and `children` may animate it.
+fn draw_caption(
+    map: &HexMap,
+    size: f32,
+    text: &str,
+    attrs: &str,
+    children: &str,
+    out: &mut String,
+) {
+    let (x, y, _, _) = view_box(map, size);
+    writeln!(
+        out,
+        "<text class=\"caption\" x=\"{:.1}\" y=\"{:.1}\" font-size=\"{:.1}\" font-family=\"sans-serif\"{attrs}>{children}{text}</text>",
+        x + size / 3.0,
+        y + size * 0.8,
+        size / 1.5
+    )
+    .unwrap();
+}
+
+// One side of a hex. Directions are indexed as in ALL_DIRECTIONS.
+fn draw_edge(
+    pos: AxialCoord,
+    dir_idx: usize,
+    size: f32,
+    color: &str,
+    width: f32,
+    out: &mut String,
+) {
+    // Corners are numbered clockwise from the lower right, so the NE side
+    // runs from corner 4 to corner 5.
+    let (x1, y1) = hex_corner(pos, (dir_idx + 4) % 6, size);
+    let (x2, y2) = hex_corner(pos, (dir_idx + 5) % 6, size);
+    writeln!(
+        out,
+        "<line x1=\"{x1:.1}\" y1=\"{y1:.1}\" x2=\"{x2:.1}\" y2=\"{y2:.1}\" stroke=\"{color}\" stroke-width=\"{width:.1}\" stroke-linecap=\"round\" />"
+    )
+    .unwrap();
+}
+
+/// Center of a (pointy-topped) hex, in SVG units.
+pub fn hex_center(pos: AxialCoord, size: f32) -> (f32, f32) {
+    let x = size * 3.0_f32.sqrt() * (pos.q as f32 + pos.r as f32 / 2.0);
+    let y = size * 1.5 * pos.r as f32;
+    (x, y)
+}
+
+fn hex_corner(pos: AxialCoord, corner: usize, size: f32) -> (f32, f32) {
+    let (cx, cy) = hex_center(pos, size);
+    let angle =
+        std::f32::consts::PI / 3.0 * corner as f32 + std::f32::consts::PI / 6.0;
+    (cx + size * angle.cos(), cy + size * angle.sin())
+}
+
+fn hex_points(pos: AxialCoord, size: f32) -> String {
+    let points: Vec<_> = (0..6)
+        .map(|i| {
+            let (x, y) = hex_corner(pos, i, size);
+            format!("{x:.1},{y:.1}")
+        })
+        .collect();
+    points.join(" ")
+}
+
+// (x, y, width, height) covering the whole map.
+fn view_box(map: &HexMap, size: f32) -> (f32, f32, f32, f32) {
+    let mut min = (f32::INFINITY, f32::INFINITY);
+    let mut max = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for (pos, _) in map.all_nodes() {
+        let (cx, cy) = hex_center(pos, size);
+        min = (min.0.min(cx), min.1.min(cy));
+        max = (max.0.max(cx), max.1.max(cy));
+    }
+    let margin = size * 1.1;
+    (
+        min.0 - margin,
+        min.1 - margin,
+        max.0 - min.0 + 2.0 * margin,
+        max.1 - min.1 + 2.0 * margin,
+    )
+}
+
+/// Fill color for a terrain type.
+pub fn terrain_color(terrain: Terrain) -> &'static str {
+    match terrain {
+        Terrain::Jungle => "#4caf50",
+        Terrain::Desert => "#f4d35e",
+        Terrain::Water => "#3d8bd9",
+        Terrain::Village => "#d9534f",
+        Terrain::Swamp => "#9e9e9e",
+        Terrain::Cave => "#8d6e63",
+        Terrain::Invalid => "#424242",
+    }
+}
+
+/// Color of a player's piece.
+pub fn player_color(player_idx: usize) -> &'static str {
+    const COLORS: [&str; 4] = ["#e53935", "#1e88e5", "#fdd835", "#8e24aa"];
+    COLORS[player_idx % COLORS.len()]
+}
+
+// What's visible after each step of a replay.
+#[derive(Clone, PartialEq)]
+struct Snapshot {
+    round_idx: usize,
+    players: Vec<(usize, AxialCoord)>,
+    // Indices into the starting barriers of those still standing.
+    barriers: Vec<usize>,
+    caves: Vec<(AxialCoord, usize)>,
+}
+
+impl Snapshot {
+    fn new(game: &GameState, start_barriers: &[Barrier]) -> Self {
+        let scene = Scene::from_game(game);
+        let barriers = (0..start_barriers.len())
+            .filter(|&i| {
+                let b = &start_barriers[i];
+                game.barriers.iter().any(|other| {
+                    other.from_board == b.from_board
+                        && other.to_board == b.to_board
+                })
+            })
+            .collect();
+        Self {
+            round_idx: game.round_idx,
+            players: scene.players,
+            barriers,
+            caves: scene.caves,
+        }
+    }
+}
+
+// Replay a record, returning its starting state and a snapshot for each
+// hex moved and each other visible change.
+fn replay_snapshots(
+    record: &GameRecord,
+) -> Result<(GameState, Vec<Snapshot>), String> {
+    let (start, _) = record.start_game().map_err(|e| e.to_string())?;
+    let mut snapshots = vec![Snapshot::new(&start, &start.barriers)];
+    record.replay_with(|game, player_idx, action, outcome| {
+        let before = snapshots.last().unwrap().clone();
+        if let RecordedAction::Action(PlayerAction::Move(mv)) = action
+            && let Some(slot) =
+                before.players.iter().position(|p| p.0 == player_idx)
+        {
+            // One step per hex, like DurangoAPI's history.
+            let ignored = match outcome {
+                ActionOutcome::IgnoreMoveIdx(idx) => Some(*idx),
+                _ => None,
+            };
+            let mut pos = before.players[slot].1;
+            let mut steps = vec![];
+            for (i, dir) in mv.path.iter().enumerate() {
+                if ignored != Some(i) {
+                    pos = dir.neighbor_coord(pos);
+                    steps.push(pos);
+                }
+            }
+            steps.pop();
+            for pos in steps {
+                let mut step = snapshots.last().unwrap().clone();
+                step.players[slot].1 = pos;
+                snapshots.push(step);
+            }
+        }
+        let after = Snapshot::new(game, &start.barriers);
+        if after != *snapshots.last().unwrap() {
+            snapshots.push(after);
+        }
+    })?;
+    Ok((start, snapshots))
+}
+
+/// One SVG image for each step of a recorded game: each hex a piece moves,
+/// and each barrier broken, cave token taken or round started.
+pub fn replay_frames(
+    record: &GameRecord,
+    options: &SvgOptions,
+) -> Result<Vec<String>, String> {
+    let (start, snapshots) = replay_snapshots(record)?;
+    let frames = snapshots
+        .iter()
+        .map(|snap| {
+            let barriers: Vec<Barrier> = snap
+                .barriers
+                .iter()
+                .map(|&i| start.barriers[i].clone())
+                .collect();
+            let scene = Scene {
+                map: &start.map,
+                barriers: &barriers,
+                players: snap.players.clone(),
+                caves: snap.caves.clone(),
+            };
+            let options = SvgOptions {
+                caption: Some(format!("Round {}", snap.round_idx + 1)),
+                ..options.clone()
+            };
+            render_svg(&scene, &options)
+        })
+        .collect();
+    Ok(frames)
+}
+
+/// A self-contained animated SVG (using SMIL) of a recorded game, showing
+/// each step for `step_secs` seconds: pieces moving hex by hex, barriers
+/// disappearing as they're broken, and caves being emptied.
+pub fn replay_svg(
+    record: &GameRecord,
+    options: &SvgOptions,
+    step_secs: f32,
+) -> Result<String, String> {
+    let (start, snapshots) = replay_snapshots(record)?;
+    let size = options.hex_size;
+    let time = |step: usize| format!("{:.2}s", step as f32 * step_secs);
+    let mut out = String::new();
+    open_svg(&start.map, options, &mut out);
+    let board = Scene {
+        caves: vec![],
+        ..Scene::from_map(&start.map)
+    };
+    draw_board(&board, options, &mut out);
+
+    // Each cave count and round number is its own text, shown while it's
+    // current.
+    for &(pos, _) in &snapshots[0].caves {
+        let counts: Vec<usize> = snapshots
+            .iter()
+            .map(|snap| {
+                snap.caves.iter().find(|c| c.0 == pos).map_or(0, |c| c.1)
+            })
+            .collect();
+        let (cx, cy) = hex_center(pos, size);
+        for (value, begin, end) in spans(&counts) {
+            let (attrs, children) = visibility(begin, end, &time);
+            writeln!(
+                out,
+                "<text class=\"cave\" x=\"{cx:.1}\" y=\"{cy:.1}\" font-size=\"{:.1}\" font-family=\"sans-serif\" dominant-baseline=\"middle\" text-anchor=\"middle\"{attrs}>{children}{value}</text>",
+                size / 2.0,
+            )
+            .unwrap();
+        }
+    }
+    for (i, barrier) in start.barriers.iter().enumerate() {
+        let broken = snapshots.iter().position(|s| !s.barriers.contains(&i));
+        let children = match broken {
+            Some(step) => format!(
+                "<set attributeName=\"visibility\" to=\"hidden\" begin=\"{}\" fill=\"freeze\" />",
+                time(step)
+            ),
+            None => String::new(),
+        };
+        draw_barrier(i, barrier, size, &children, &mut out);
+    }
+    let rounds: Vec<usize> = snapshots.iter().map(|s| s.round_idx).collect();
+    for (round_idx, begin, end) in spans(&rounds) {
+        let text = format!("Round {}", round_idx + 1);
+        let (attrs, children) = visibility(begin, end, &time);
+        draw_caption(&start.map, size, &text, &attrs, &children, &mut out);
+    }
+
+    // Pieces slide between their positions at each step, and vanish if
+    // their player resigns.
+    let num_steps = snapshots.len();
+    for &(player_idx, _) in &snapshots[0].players {
+        let centers: Vec<Option<(f32, f32)>> = snapshots
+            .iter()
+            .map(|snap| {
+                piece_centers(&snap.players, size)
+                    .into_iter()
+                    .find(|p| p.0 == player_idx)
+                    .map(|p| p.1)
+            })
+            .collect();
+        let resigned = centers.iter().position(Option::is_none);
+        let centers: Vec<(f32, f32)> = centers.into_iter().flatten().collect();
+        let mut children = String::new();
+        if num_steps > 1 && centers.len() > 1 {
+            let values: Vec<_> = centers
+                .iter()
+                .map(|(x, y)| format!("{x:.1},{y:.1}"))
+                .collect();
+            let key_times: Vec<_> = (0..centers.len())
+                .map(|i| {
+                    format!("{:.4}", i as f32 / (centers.len() - 1) as f32)
+                })
+                .collect();
+            write!(
+                children,
+                "<animateTransform attributeName=\"transform\" type=\"translate\" dur=\"{}\" values=\"{}\" keyTimes=\"{}\" fill=\"freeze\" />",
+                time(centers.len() - 1),
+                values.join(";"),
+                key_times.join(";")
+            )
+            .unwrap();
+        }
+        if let Some(step) = resigned {
+            write!(
+                children,
+                "<set attributeName=\"visibility\" to=\"hidden\" begin=\"{}\" fill=\"freeze\" />",
+                time(step)
+            )
+            .unwrap();
+        }
+        let (cx, cy) = centers[0];
+        draw_piece(player_idx, cx, cy, size, &children, &mut out);
+    }
+    out.push_str("</svg>\n");
+    Ok(out)
+}
+
+// Runs of equal values, as (value, first step, step after the run).
+fn spans<T: Copy + PartialEq>(values: &[T]) -> Vec<(T, usize, Option<usize>)> {
+    let mut spans: Vec<(T, usize, Option<usize>)> = vec![];
+    for (step, &value) in values.iter().enumerate() {
+        match spans.last_mut() {
+            Some(last) if last.0 == value => {}
+            Some(last) => {
+                last.2 = Some(step);
+                spans.push((value, step, None));
+            }
+            None => spans.push((value, step, None)),
+        }
+    }
+    spans
+}
+
+// Attributes and child elements that show an element from step `begin`
+// until step `end`.
+fn visibility(
+    begin: usize,
+    end: Option<usize>,
+    time: &impl Fn(usize) -> String,
+) -> (String, String) {
+    let set = |to: &str, step: usize| {
+        format!(
+            "<set attributeName=\"visibility\" to=\"{to}\" begin=\"{}\" fill=\"freeze\" />",
+            time(step)
+        )
+    };
+    let mut attrs = String::new();
+    let mut children = String::new();
+    if begin > 0 {
+        attrs.push_str(" visibility=\"hidden\"");
+        children.push_str(&set("visible", begin));
+    }
+    if let Some(end) = end {
+        children.push_str(&set("hidden", end));
+    }
+    (attrs, children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::create_agent;
+    use crate::rules::RuleSet;
+
+    #[test]
+    fn renders_game() {
+        let rng = &mut rand::rng();
+        let game =
+            GameState::new(3, "easy1", &RuleSet::default(), rng).unwrap();
+        let options = SvgOptions {
+            path: vec![game.players[0].position],
+            overlay: vec![game.players[1].position],
+            ..Default::default()
+        };
+        let svg = game_svg(&game, &options);
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("class=\"piece\"").count(), 3);
+        assert_eq!(
+            svg.matches("class=\"barrier\"").count(),
+            game.barriers.len()
+        );
+        assert!(svg.contains("class=\"path\""));
+        assert!(svg.contains("class=\"overlay\""));
+        let view = PlayerView::new(&game, 1, &[]);
+        assert_eq!(view_svg(&view, &options), svg);
+    }
+
+    #[test]
+    fn replays_record() {
+        let players = vec!["a".to_string(), "b".to_string()];
+        let mut record =
+            GameRecord::new("first", 3, players, RuleSet::default());
+        let (mut game, mut rng) = record.start_game().unwrap();
+        let agent_rng = &mut rand::rng();
+        let mut agent = create_agent(1);
+        for _ in 0..30 {
+            let (round_idx, player_idx) =
+                (game.round_idx, game.curr_player_idx);
+            let action = agent.choose_action(&game, agent_rng);
+            game.process_action(&action, &mut rng).unwrap();
+            record.add(round_idx, player_idx, RecordedAction::Action(action));
+        }
+        let options = SvgOptions::default();
+        let frames = replay_frames(&record, &options).unwrap();
+        assert!(frames.len() > 2);
+        // The last frame shows the final state, though barriers may be in
+        // a different order.
+        let last = frames.last().unwrap();
+        let expected = game_svg(&game, &options);
+        let pieces = |svg: &str| {
+            svg.lines()
+                .filter(|l| l.contains("class=\"piece\""))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(pieces(last), pieces(&expected));
+        assert_eq!(
+            last.matches("class=\"barrier\"").count(),
+            game.barriers.len()
+        );
+
+        let svg = replay_svg(&record, &options, 0.25).unwrap();
+        assert_eq!(svg.matches("<animateTransform").count(), 2);
+        assert_eq!(
+            svg.matches("<text").count(),
+            svg.matches("</text>").count()
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crate::cards::Card;
+use crate::data::{ALL_BARRIER_TYPES, Terrain};
+use serde::{Deserialize, Serialize};
+
+/// Core game constants, which can be changed to try house rules and variants.
+/// Any fields not specified when deserializing use the standard rules.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RuleSet {
+    // Number of cards drawn at the end of each turn.
+    pub hand_size: usize,
+    // Cards each player starts with.
+    pub starting_deck: Vec<Card>,
+    // Number of cards in a full shop. The storage opens when the shop isn't full.
+    pub shop_size: usize,
+    // Number of bonus tokens placed in each cave.
+    pub tokens_per_cave: usize,
+    // Pool of barriers to place between boards, drawn at random.
+    pub barrier_types: Vec<(Terrain, u8)>,
+    // Allowed range of player counts (inclusive).
+    pub min_players: usize,
+    pub max_players: usize,
+    // If set, the game ends after this many rounds even if nobody finished.
+    pub max_rounds: Option<usize>,
+    // Number of Curse cards, handed out by Hexers until they run out.
+    pub curses: usize,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            hand_size: 4,
+            starting_deck: vec![
+                Card::explorer(),
+                Card::explorer(),
+                Card::explorer(),
+                Card::traveler(),
+                Card::traveler(),
+                Card::traveler(),
+                Card::traveler(),
+                Card::sailor(),
+            ],
+            shop_size: 6,
+            tokens_per_cave: 4,
+            barrier_types: ALL_BARRIER_TYPES.to_vec(),
+            min_players: 2,
+            max_players: 4,
+            max_rounds: None,
+            curses: 10,
+        }
+    }
+}
+
+impl RuleSet {
+    /// Check that these rules describe a playable game.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.hand_size == 0 {
+            return Err("Hand size must be positive".into());
+        }
+        if self.starting_deck.len() < self.hand_size {
+            return Err(format!(
+                "Starting deck has {} cards, but hand size is {}",
+                self.starting_deck.len(),
+                self.hand_size
+            ));
+        }
+        if self.shop_size == 0 {
+            return Err("Shop size must be positive".into());
+        }
+        if self.tokens_per_cave == 0 {
+            return Err("Caves must hold at least one token".into());
+        }
+        for &(terrain, cost) in &self.barrier_types {
+            if !matches!(
+                terrain,
+                Terrain::Jungle
+                    | Terrain::Desert
+                    | Terrain::Water
+                    | Terrain::Swamp
+            ) || cost == 0
+            {
+                return Err(format!(
+                    "Invalid barrier type: {terrain:?} {cost}"
+                ));
+            }
+        }
+        if self.max_rounds == Some(0) {
+            return Err("Round limit must be positive".into());
+        }
+        if self.min_players == 0 || self.min_players > self.max_players {
+            return Err(format!(
+                "Invalid player range: {}..={}",
+                self.min_players, self.max_players
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_rules() {
+        let rules: RuleSet =
+            serde_json::from_str(r#"{"hand_size": 5, "max_players": 6}"#)
+                .unwrap();
+        assert_eq!(rules.hand_size, 5);
+        assert_eq!(rules.max_players, 6);
+        assert_eq!(rules.starting_deck.len(), 8);
+        assert!(rules.validate().is_ok());
+
+        let rules: RuleSet =
+            serde_json::from_str(r#"{"hand_size": 9}"#).unwrap();
+        assert!(rules.validate().is_err());
+    }
+}
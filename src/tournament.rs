@@ -0,0 +1,503 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::agent::AgentSpec;
+use crate::game::{ActionOutcome, GameState};
+use crate::rules::RuleSet;
+
+/// A named agent configuration taking part in a tournament.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Entrant {
+    pub name: String,
+    pub agent: AgentSpec,
+}
+
+/// Which entrants play each other.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    // Every entrant plays every other entrant.
+    #[default]
+    RoundRobin,
+    // The first entrant plays each of the others.
+    Gauntlet,
+}
+
+/// Settings shared by all games in a tournament. Games are head-to-head,
+/// and each pairing plays from both seats on every layout.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TournamentConfig {
+    pub format: Format,
+    // Named layouts to play on, e.g. "easy1".
+    pub layouts: Vec<String>,
+    // Games per pairing on each layout, split evenly between seat orders.
+    pub games_per_pairing: usize,
+    pub rules: RuleSet,
+    // Number of games to run in parallel.
+    pub threads: usize,
+    // Games still running after this many actions are scored as draws.
+    pub max_actions: usize,
+    // If set, each game's random state is derived from this seed.
+    pub seed: Option<u64>,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> Self {
+        Self {
+            format: Format::RoundRobin,
+            layouts: vec!["easy1".into()],
+            games_per_pairing: 2,
+            rules: RuleSet::default(),
+            threads: std::thread::available_parallelism()
+                .map_or(1, |n| n.get()),
+            max_actions: 2000,
+            seed: None,
+        }
+    }
+}
+
+/// The outcome of one game.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GameResult {
+    pub layout: String,
+    // Entrant index in each seat.
+    pub seats: [usize; 2],
+    // Entrant index of the winner, or None for a draw.
+    pub winner: Option<usize>,
+    pub rounds: usize,
+    // Problems the agents worked around, e.g. an external bot that failed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agent_errors: Vec<String>,
+}
+
+/// An entrant's results and estimated rating.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Rating {
+    pub name: String,
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    // Bradley-Terry strength on the Elo scale, with a mean of zero.
+    pub elo: f64,
+    // Half-width of an approximate 95% confidence interval for `elo`, or
+    // None if the entrant played no games.
+    pub elo_ci95: Option<f64>,
+}
+
+/// All results of a tournament, with ratings sorted from best to worst.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TournamentReport {
+    pub results: Vec<GameResult>,
+    pub ratings: Vec<Rating>,
+}
+
+// A game to be played: layout index, and entrant index per seat.
+#[derive(Clone, Copy)]
+struct Job {
+    layout: usize,
+    seats: [usize; 2],
+}
+
+/// Run a tournament between the given entrants.
+pub fn run_tournament(
+    entrants: &[Entrant],
+    config: &TournamentConfig,
+) -> Result<TournamentReport, String> {
+    if entrants.len() < 2 {
+        return Err("A tournament needs at least two entrants".into());
+    }
+    if config.layouts.is_empty() {
+        return Err("A tournament needs at least one layout".into());
+    }
+    let pairings: Vec<(usize, usize)> = match config.format {
+        Format::RoundRobin => (0..entrants.len())
+            .flat_map(|a| (a + 1..entrants.len()).map(move |b| (a, b)))
+            .collect(),
+        Format::Gauntlet => (1..entrants.len()).map(|b| (0, b)).collect(),
+    };
+    let mut jobs = Vec::new();
+    for (layout, _) in config.layouts.iter().enumerate() {
+        for &(a, b) in &pairings {
+            for i in 0..config.games_per_pairing {
+                // Alternate seat orders.
+                let seats = if i % 2 == 0 { [a, b] } else { [b, a] };
+                jobs.push(Job { layout, seats });
+            }
+        }
+    }
+    let results = play_jobs(entrants, config, &jobs, 0)?;
+    let ratings = rate(entrants, &results);
+    Ok(TournamentReport { results, ratings })
+}
+
+// Play all jobs on worker threads, returning results in job order.
+// `first_game` offsets the per-game seeds.
+fn play_jobs(
+    entrants: &[Entrant],
+    config: &TournamentConfig,
+    jobs: &[Job],
+    first_game: usize,
+) -> Result<Vec<GameResult>, String> {
+    let next_job = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; jobs.len()]);
+    let error = Mutex::new(None);
+    std::thread::scope(|scope| {
+        for _ in 0..config.threads.clamp(1, jobs.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let i = next_job.fetch_add(1, Ordering::Relaxed);
+                    let Some(&job) = jobs.get(i) else { break };
+                    let mut rng = match config.seed {
+                        Some(seed) => StdRng::seed_from_u64(
+                            seed.wrapping_add((first_game + i) as u64),
+                        ),
+                        None => StdRng::from_rng(&mut rand::rng()),
+                    };
+                    match play_game(entrants, config, job, &mut rng) {
+                        Ok(res) => results.lock().unwrap()[i] = Some(res),
+                        Err(e) => {
+                            *error.lock().unwrap() = Some(e);
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    if let Some(e) = error.into_inner().unwrap() {
+        return Err(e);
+    }
+    Ok(results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+fn play_game(
+    entrants: &[Entrant],
+    config: &TournamentConfig,
+    job: Job,
+    rng: &mut StdRng,
+) -> Result<GameResult, String> {
+    let layout = &config.layouts[job.layout];
+    let mut game = GameState::new(2, layout, &config.rules, rng)
+        .map_err(|e| e.to_string())?;
+    let mut agents = job.seats.map(|entrant| entrants[entrant].agent.create());
+    for (i, agent) in agents.iter_mut().enumerate() {
+        agent.on_game_start(&game, i);
+    }
+    let mut winner_seat = None;
+    for _ in 0..config.max_actions {
+        let seat = game.curr_player_idx;
+        let action = agents[seat].choose_action(&game, rng);
+        let outcome = match game.process_action(&action, rng) {
+            Ok(outcome) => outcome,
+            Err(_) => {
+                // Invalid actions forfeit the game.
+                winner_seat = Some(1 - seat);
+                break;
+            }
+        };
+        for agent in agents.iter_mut() {
+            agent.on_action(&game, seat, &action, &outcome);
+        }
+        if matches!(outcome, ActionOutcome::GameOver) {
+            let scores = game.player_scores();
+            if scores[0] != scores[1] {
+                winner_seat = Some(if scores[0] > scores[1] { 0 } else { 1 });
+            }
+            break;
+        }
+    }
+    let mut agent_errors = vec![];
+    for (agent, &entrant) in agents.iter_mut().zip(&job.seats) {
+        agent.on_game_end(&game);
+        let name = &entrants[entrant].name;
+        agent_errors.extend(
+            agent
+                .take_errors()
+                .into_iter()
+                .map(|e| format!("{name}: {e}")),
+        );
+    }
+    Ok(GameResult {
+        layout: layout.clone(),
+        seats: job.seats,
+        winner: winner_seat.map(|s| job.seats[s]),
+        rounds: game.round_idx,
+        agent_errors,
+    })
+}
+
+// Fit Bradley-Terry strengths with the MM algorithm, counting draws as half a
+// win for each side.
+fn rate(entrants: &[Entrant], results: &[GameResult]) -> Vec<Rating> {
+    let n = entrants.len();
+    // wins[i][j]: points scored by i against j.
+    let mut wins = vec![vec![0.0; n]; n];
+    let mut ratings: Vec<Rating> = entrants
+        .iter()
+        .map(|e| Rating {
+            name: e.name.clone(),
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            elo: 0.0,
+            elo_ci95: None,
+        })
+        .collect();
+    for res in results {
+        let [a, b] = res.seats;
+        match res.winner {
+            Some(w) => {
+                let l = if w == a { b } else { a };
+                wins[w][l] += 1.0;
+                ratings[w].wins += 1;
+                ratings[l].losses += 1;
+            }
+            None => {
+                wins[a][b] += 0.5;
+                wins[b][a] += 0.5;
+                ratings[a].draws += 1;
+                ratings[b].draws += 1;
+            }
+        }
+    }
+    let games = |i: usize, j: usize| wins[i][j] + wins[j][i];
+    // A small prior (one drawn game against a virtual average opponent)
+    // keeps strengths finite for undefeated or winless entrants.
+    const PRIOR: f64 = 0.5;
+    let mut strength = vec![1.0; n];
+    for _ in 0..1000 {
+        let mut next = strength.clone();
+        for i in 0..n {
+            let total_wins: f64 = wins[i].iter().sum::<f64>() + PRIOR;
+            let denom: f64 = (0..n)
+                .filter(|&j| j != i)
+                .map(|j| games(i, j) / (strength[i] + strength[j]))
+                .sum::<f64>()
+                + 2.0 * PRIOR / (strength[i] + 1.0);
+            next[i] = total_wins / denom;
+        }
+        // Normalize to a geometric mean of one.
+        let log_mean = next.iter().map(|s| s.ln()).sum::<f64>() / n as f64;
+        for s in next.iter_mut() {
+            *s /= log_mean.exp();
+        }
+        let delta = (0..n)
+            .map(|i| (next[i] - strength[i]).abs())
+            .fold(0.0, f64::max);
+        strength = next;
+        if delta < 1e-9 {
+            break;
+        }
+    }
+    let elo_per_nat = 400.0 / std::f64::consts::LN_10;
+    for i in 0..n {
+        // Fisher information for log-strength i, holding the others fixed.
+        let info: f64 = (0..n)
+            .filter(|&j| j != i)
+            .map(|j| {
+                let p = strength[i] / (strength[i] + strength[j]);
+                games(i, j) * p * (1.0 - p)
+            })
+            .sum();
+        ratings[i].elo = strength[i].ln() * elo_per_nat;
+        if info > 0.0 {
+            ratings[i].elo_ci95 = Some(1.96 * elo_per_nat / info.sqrt());
+        }
+    }
+    ratings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+    ratings
+}
+
+/// Settings for a sequential probability ratio test of whether a candidate
+/// agent is at least `elo1` stronger than a baseline (H1), or at most `elo0`
+/// stronger (H0).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SprtConfig {
+    pub elo0: f64,
+    pub elo1: f64,
+    // False positive and false negative rates.
+    pub alpha: f64,
+    pub beta: f64,
+    // Give up without a decision after this many games.
+    pub max_games: usize,
+}
+
+impl Default for SprtConfig {
+    fn default() -> Self {
+        Self {
+            elo0: 0.0,
+            elo1: 20.0,
+            alpha: 0.05,
+            beta: 0.05,
+            max_games: 10_000,
+        }
+    }
+}
+
+/// Result of an SPRT run, from the candidate's point of view.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SprtReport {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    // Log-likelihood ratio of H1 vs H0, and the bounds for accepting each.
+    pub llr: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    // Some(true) if H1 was accepted (the candidate is stronger), Some(false)
+    // if H0 was accepted, or None if the game limit was reached first.
+    pub accepted_h1: Option<bool>,
+}
+
+impl SprtConfig {
+    /// Evaluate the test for the given results so far.
+    pub fn evaluate(
+        &self,
+        wins: usize,
+        losses: usize,
+        draws: usize,
+    ) -> SprtReport {
+        let lower_bound = (self.beta / (1.0 - self.alpha)).ln();
+        let upper_bound = ((1.0 - self.beta) / self.alpha).ln();
+        let mut llr = 0.0;
+        if wins + losses + draws > 0 {
+            // Normal approximation to the trinomial GSPRT. Half a game of
+            // each outcome is added so one-sided results have some variance.
+            let (w, l, d) =
+                (wins as f64 + 0.5, losses as f64 + 0.5, draws as f64 + 0.5);
+            let n = w + l + d;
+            let score = (w + 0.5 * d) / n;
+            let var = (w * (1.0 - score).powi(2)
+                + l * score.powi(2)
+                + d * (0.5 - score).powi(2))
+                / n;
+            let expected = |elo: f64| 1.0 / (1.0 + 10f64.powf(-elo / 400.0));
+            let (s0, s1) = (expected(self.elo0), expected(self.elo1));
+            llr = (s1 - s0) * (2.0 * score - s0 - s1) * n / (2.0 * var);
+        }
+        let accepted_h1 = if llr >= upper_bound {
+            Some(true)
+        } else if llr <= lower_bound {
+            Some(false)
+        } else {
+            None
+        };
+        SprtReport {
+            wins,
+            losses,
+            draws,
+            llr,
+            lower_bound,
+            upper_bound,
+            accepted_h1,
+        }
+    }
+}
+
+/// Play `candidate` against `baseline` in batches of games, alternating
+/// seats and layouts, until the SPRT reaches a decision.
+pub fn run_sprt(
+    candidate: &Entrant,
+    baseline: &Entrant,
+    config: &TournamentConfig,
+    sprt: &SprtConfig,
+) -> Result<SprtReport, String> {
+    if config.layouts.is_empty() {
+        return Err("SPRT needs at least one layout".into());
+    }
+    let entrants = [candidate.clone(), baseline.clone()];
+    let batch_size = config.threads.max(1) * 2;
+    let (mut wins, mut losses, mut draws) = (0, 0, 0);
+    let mut num_games = 0;
+    loop {
+        let report = sprt.evaluate(wins, losses, draws);
+        if report.accepted_h1.is_some() || num_games >= sprt.max_games {
+            return Ok(report);
+        }
+        let jobs: Vec<Job> = (num_games..num_games + batch_size)
+            .map(|i| Job {
+                layout: (i / 2) % config.layouts.len(),
+                seats: if i % 2 == 0 { [0, 1] } else { [1, 0] },
+            })
+            .collect();
+        for res in play_jobs(&entrants, config, &jobs, num_games)? {
+            match res.winner {
+                Some(0) => wins += 1,
+                Some(_) => losses += 1,
+                None => draws += 1,
+            }
+        }
+        num_games += jobs.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_robin() {
+        let entrants = [
+            Entrant {
+                name: "random".into(),
+                agent: AgentSpec::Random,
+            },
+            Entrant {
+                name: "greedy".into(),
+                agent: AgentSpec::Greedy,
+            },
+        ];
+        let config = TournamentConfig {
+            layouts: vec!["first".into(), "easy1".into()],
+            games_per_pairing: 4,
+            threads: 2,
+            seed: Some(7),
+            ..Default::default()
+        };
+        let report = run_tournament(&entrants, &config).unwrap();
+        assert_eq!(report.results.len(), 8);
+        // Seats are rotated.
+        let first_seats = report.results.iter().filter(|r| r.seats[0] == 0);
+        assert_eq!(first_seats.count(), 4);
+        let total: usize = report
+            .ratings
+            .iter()
+            .map(|r| r.wins + r.losses + r.draws)
+            .sum();
+        assert_eq!(total, 16);
+        let elo_sum: f64 = report.ratings.iter().map(|r| r.elo).sum();
+        assert!(elo_sum.abs() < 1e-6);
+        // Saved reports can be read back.
+        let json = serde_json::to_string(&report).unwrap();
+        let loaded: TournamentReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.results.len(), report.results.len());
+        for (a, b) in loaded.ratings.iter().zip(&report.ratings) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.elo_ci95, b.elo_ci95);
+            assert!(a.elo_ci95.is_some());
+        }
+        // Entrants without games have no interval.
+        let unplayed = rate(&entrants, &[]);
+        assert!(unplayed.iter().all(|r| r.elo_ci95.is_none()));
+        let json = serde_json::to_string(&unplayed).unwrap();
+        let loaded: Vec<Rating> = serde_json::from_str(&json).unwrap();
+        assert!(loaded.iter().all(|r| r.elo_ci95.is_none()));
+    }
+
+    #[test]
+    fn sprt_decisions() {
+        let sprt = SprtConfig::default();
+        assert_eq!(sprt.evaluate(0, 0, 0).accepted_h1, None);
+        assert_eq!(sprt.evaluate(300, 100, 50).accepted_h1, Some(true));
+        assert_eq!(sprt.evaluate(100, 300, 50).accepted_h1, Some(false));
+    }
+}
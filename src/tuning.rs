@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentSpec;
+use crate::tournament::{Entrant, Format, TournamentConfig, run_tournament};
+
+/// Settings for tuning a planner's evaluation weights by self-play.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TuningConfig {
+    // Number of passes over all the weights.
+    pub passes: usize,
+    // Relative change tried for each weight, e.g. 0.25 for +/-25%.
+    pub step: f64,
+    // The step is multiplied by this after a pass with no improvement.
+    pub shrink: f64,
+    // Fraction of points a candidate must score against the current best
+    // weights to replace them.
+    pub min_score: f64,
+    // Settings for each match. The format is ignored, and a fixed seed
+    // makes every match use the same games, which reduces noise.
+    pub matches: TournamentConfig,
+}
+
+impl Default for TuningConfig {
+    fn default() -> Self {
+        Self {
+            passes: 3,
+            step: 0.25,
+            shrink: 0.5,
+            min_score: 0.55,
+            matches: TournamentConfig {
+                games_per_pairing: 20,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Tune the evaluation weights of `base` (a turn planner with EvalWeights) by
+/// coordinate descent: each weight in turn is nudged up or down, and the
+/// change is kept if the new weights beat the current ones in a match.
+/// `on_improve` is called with the new best spec, the weight that changed,
+/// and the winning score, e.g. to save progress.
+pub fn tune_weights(
+    base: &AgentSpec,
+    config: &TuningConfig,
+    mut on_improve: impl FnMut(&AgentSpec, &'static str, f64),
+) -> Result<AgentSpec, String> {
+    let mut best = base.clone();
+    let num_params = match best.weights_mut() {
+        Some(weights) => weights.params_mut().len(),
+        None => return Err(format!("Agent {base:?} has no weights to tune")),
+    };
+    let mut step = config.step;
+    for _ in 0..config.passes {
+        let mut improved = false;
+        for i in 0..num_params {
+            for sign in [1.0, -1.0] {
+                let mut candidate = best.clone();
+                let weights = candidate.weights_mut().unwrap();
+                let (name, w) =
+                    weights.params_mut().into_iter().nth(i).unwrap();
+                // Zero weights can't be scaled, so step away from zero.
+                *w = if *w == 0.0 {
+                    sign * step
+                } else {
+                    *w * (1.0 + sign * step)
+                };
+                let score = match_score(&candidate, &best, &config.matches)?;
+                if score >= config.min_score {
+                    best = candidate;
+                    improved = true;
+                    on_improve(&best, name, score);
+                    break;
+                }
+            }
+        }
+        if !improved {
+            step *= config.shrink;
+        }
+    }
+    Ok(best)
+}
+
+// Fraction of points scored by `candidate` in a match against `baseline`.
+fn match_score(
+    candidate: &AgentSpec,
+    baseline: &AgentSpec,
+    config: &TournamentConfig,
+) -> Result<f64, String> {
+    let entrants = [
+        Entrant {
+            name: "candidate".into(),
+            agent: candidate.clone(),
+        },
+        Entrant {
+            name: "baseline".into(),
+            agent: baseline.clone(),
+        },
+    ];
+    let config = TournamentConfig {
+        format: Format::Gauntlet,
+        ..config.clone()
+    };
+    let report = run_tournament(&entrants, &config)?;
+    let points: f64 = report
+        .results
+        .iter()
+        .map(|r| match r.winner {
+            Some(0) => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        })
+        .sum();
+    Ok(points / report.results.len().max(1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{EvalWeights, SearchBudget};
+
+    #[test]
+    fn coordinate_descent() {
+        let base = AgentSpec::Static {
+            cost_exponent: 0,
+            budget: SearchBudget::iterations(20),
+            weights: EvalWeights::default(),
+        };
+        // Accept every change, so each weight moves once.
+        let config = TuningConfig {
+            passes: 1,
+            min_score: 0.0,
+            matches: TournamentConfig {
+                layouts: vec!["first".into()],
+                games_per_pairing: 2,
+                max_actions: 200,
+                seed: Some(3),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut changed = vec![];
+        let mut tuned =
+            tune_weights(&base, &config, |_, name, _| changed.push(name))
+                .unwrap();
+        assert_eq!(changed.len(), 12);
+        let weights = tuned.weights_mut().unwrap();
+        assert_eq!(weights.token, 12.5);
+        assert_eq!(weights.distance, -1250.0);
+
+        assert!(
+            tune_weights(&AgentSpec::Greedy, &config, |_, _, _| {}).is_err()
+        );
+    }
+}
@@ -0,0 +1,344 @@
+use crate::cards::{BuyableCard, Card};
+use crate::data::{
+    self, AxialCoord, Barrier, BonusToken, BrokenBarrier, HexMap,
+};
+use crate::game::{EndReason, GameState};
+use crate::graph::HexGraph;
+use crate::player::{Player, count_cards};
+use crate::rules::RuleSet;
+use rand::prelude::{IndexedRandom, SliceRandom};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// A view of another player's public information.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PublicPlayerInfo {
+    pub player_idx: usize,
+    pub position: AxialCoord,
+    pub hand_size: usize,
+    pub played: Vec<Card>,
+    pub deck_size: usize,
+    pub discard_size: usize,
+    // The face-up discard pile, and the cards nobody can see: the deck and,
+    // for other players, the hand. Anyone could work these out by watching
+    // the game, but not their order or which cards are in the hand.
+    #[serde(default)]
+    pub discard: Vec<Card>,
+    #[serde(default)]
+    pub unseen_cards: Vec<(Card, usize)>,
+    pub tokens: Vec<BonusToken>,
+    // Tokens already spent, so they can't still be in a cave.
+    #[serde(default)]
+    pub used_tokens: Vec<BonusToken>,
+    pub broken_barriers: Vec<BrokenBarrier>,
+    #[serde(default)]
+    pub visited_caves: Vec<AxialCoord>,
+    pub resigned: bool,
+    pub is_ai: bool,
+}
+
+/// A view of my player's visible information.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MyPlayer {
+    // Public info.
+    #[serde(flatten)]
+    pub info: PublicPlayerInfo,
+    // Private info for my eyes only.
+    pub hand: Vec<Card>,
+    pub trashes: usize,
+    pub can_buy: bool,
+}
+
+/// A view of the game state for a specific player. This is everything an
+/// agent may fairly use to choose its actions.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerView {
+    pub map: HexMap,
+    pub barriers: Vec<Barrier>,
+    pub my_player: MyPlayer,
+    pub other_players: Vec<PublicPlayerInfo>,
+    pub bonuses: Vec<(AxialCoord, usize)>,
+    pub shop: Vec<BuyableCard>,
+    pub storage: Vec<BuyableCard>,
+    // Curse cards left to hand out.
+    #[serde(default)]
+    pub curses: usize,
+    pub round_idx: usize,
+    pub curr_player_idx: usize,
+    pub winner: Option<usize>,
+    pub end_reason: Option<EndReason>,
+    pub rules: RuleSet,
+}
+
+impl PlayerView {
+    /// The view of `game` for player `player_idx`. Seats marked in `ai_seats`
+    /// are shown as played by an AI.
+    pub fn new(game: &GameState, player_idx: usize, ai_seats: &[bool]) -> Self {
+        let winner = if game.end_reason.is_some() {
+            game.player_scores()
+                .iter()
+                .enumerate()
+                .max_by_key(|&(_, score)| score)
+                .map(|(i, _)| i)
+        } else {
+            None
+        };
+        let mut other_players = game
+            .players
+            .iter()
+            .enumerate()
+            .map(|(idx, p)| PublicPlayerInfo {
+                player_idx: idx,
+                position: p.position,
+                hand_size: p.hand.len(),
+                played: p.played.clone(),
+                deck_size: p.deck_size(),
+                discard_size: p.discard.len(),
+                discard: p.discard.clone(),
+                unseen_cards: if idx == player_idx {
+                    p.deck_cards()
+                } else {
+                    count_cards(p.deck.iter().chain(&p.hand).collect())
+                }
+                .into_iter()
+                .map(|(c, n)| (c.clone(), n))
+                .collect(),
+                tokens: p.tokens.clone(),
+                used_tokens: p.used_tokens.clone(),
+                broken_barriers: p.broken_barriers.clone(),
+                visited_caves: p.visited_caves.clone(),
+                resigned: p.resigned,
+                is_ai: ai_seats.get(idx).copied().unwrap_or(false),
+            })
+            .collect::<Vec<_>>();
+        let player = &game.players[player_idx];
+        let my_player = MyPlayer {
+            info: other_players.swap_remove(player_idx),
+            hand: player.hand.clone(),
+            trashes: player.trashes,
+            can_buy: player.can_buy,
+        };
+        PlayerView {
+            map: game.map.as_ref().clone(),
+            barriers: game.barriers.clone(),
+            my_player,
+            other_players,
+            bonuses: game
+                .bonus_counts()
+                .into_iter()
+                .map(|(&pos, n)| (pos, n))
+                .collect(),
+            shop: game.shop.clone(),
+            storage: game.storage.clone(),
+            curses: game.curses,
+            round_idx: game.round_idx,
+            curr_player_idx: game.curr_player_idx,
+            winner,
+            end_reason: game.end_reason,
+            rules: game.rules.as_ref().clone(),
+        }
+    }
+}
+
+/// Produces full game states consistent with a `PlayerView`, filling in the
+/// hidden information (the order of each deck, other players' hands and the
+/// tokens in each cave) at random.
+pub struct ViewSampler {
+    base: GameState,
+    me: usize,
+    // Tokens that may still be in caves: all tokens not held or already used
+    // by a player.
+    token_pool: Vec<BonusToken>,
+    // Number of tokens in each cave.
+    cave_counts: Vec<usize>,
+}
+
+impl ViewSampler {
+    pub fn new(view: &PlayerView) -> Result<Self, String> {
+        let map = Arc::new(view.map.clone());
+        let graph = Arc::new(HexGraph::new(&map));
+        Self::with_board(view, map, graph)
+    }
+
+    /// Update for a new view of the same game, reusing the board data.
+    pub fn update(&mut self, view: &PlayerView) -> Result<(), String> {
+        let map = self.base.map.clone();
+        let graph = self.base.graph.clone();
+        *self = Self::with_board(view, map, graph)?;
+        Ok(())
+    }
+
+    fn with_board(
+        view: &PlayerView,
+        map: Arc<HexMap>,
+        graph: Arc<HexGraph>,
+    ) -> Result<Self, String> {
+        let mut infos = view
+            .other_players
+            .iter()
+            .chain([&view.my_player.info])
+            .collect::<Vec<_>>();
+        infos.sort_by_key(|p| p.player_idx);
+        let mut players = Vec::with_capacity(infos.len());
+        for (i, info) in infos.into_iter().enumerate() {
+            if info.player_idx != i {
+                return Err(format!("Missing player {i} in view"));
+            }
+            let mut deck = info
+                .unseen_cards
+                .iter()
+                .flat_map(|(card, n)| std::iter::repeat_n(card.clone(), *n))
+                .collect::<Vec<_>>();
+            let is_me = i == view.my_player.info.player_idx;
+            let hidden_hand = if is_me { 0 } else { info.hand_size };
+            if deck.len() != info.deck_size + hidden_hand {
+                return Err(format!(
+                    "Player {i} has {} unseen cards, but deck size {} and \
+                     hand size {}",
+                    deck.len(),
+                    info.deck_size,
+                    info.hand_size
+                ));
+            }
+            // Other players' hands are dealt for real in `sample`.
+            let hand = if is_me {
+                view.my_player.hand.clone()
+            } else {
+                deck.split_off(info.deck_size)
+            };
+            let mut p = Player::from_piles(
+                info.position,
+                deck,
+                hand,
+                info.played.clone(),
+                info.discard.clone(),
+            );
+            p.tokens = info.tokens.clone();
+            p.used_tokens = info.used_tokens.clone();
+            p.broken_barriers = info.broken_barriers.clone();
+            p.visited_caves = info.visited_caves.clone();
+            p.resigned = info.resigned;
+            players.push(p);
+        }
+        let me = &mut players[view.my_player.info.player_idx];
+        me.trashes = view.my_player.trashes;
+        me.can_buy = view.my_player.can_buy;
+
+        let mut token_pool = data::ALL_BONUS_TOKENS.to_vec();
+        for p in &players {
+            for token in p.tokens.iter().chain(&p.used_tokens) {
+                if let Some(i) = token_pool.iter().position(|t| t == token) {
+                    token_pool.swap_remove(i);
+                }
+            }
+        }
+        Ok(Self {
+            base: GameState::from_view(view, map, graph, players),
+            me: view.my_player.info.player_idx,
+            token_pool,
+            cave_counts: view.bonuses.iter().map(|&(_, n)| n).collect(),
+        })
+    }
+
+    /// Sample a full game state that matches the view.
+    pub fn sample(&self, rng: &mut dyn rand::RngCore) -> GameState {
+        let mut game = self.base.clone();
+        for (i, p) in game.players.iter_mut().enumerate() {
+            if i == self.me {
+                p.shuffle_deck(rng);
+            } else {
+                p.redeal_hand(rng);
+            }
+        }
+        let mut pool = self.token_pool.clone();
+        pool.shuffle(rng);
+        let caves = game.bonuses_mut().iter_mut().zip(&self.cave_counts);
+        for ((_, tokens), &count) in caves {
+            for _ in 0..count {
+                // The pool holds every token not held or used by a player, so
+                // it only runs short if the view has more cave tokens than
+                // exist.
+                let token = pool.pop().unwrap_or_else(|| {
+                    *data::ALL_BONUS_TOKENS.choose(rng).unwrap()
+                });
+                tokens.push(token);
+            }
+        }
+        game.rehash();
+        game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{DrawAction, PlayerAction};
+
+    #[test]
+    fn sample_from_view() {
+        let rng = &mut rand::rng();
+        let mut game =
+            GameState::new(3, "easy1", &RuleSet::default(), rng).unwrap();
+        game.process_action(&PlayerAction::Discard(vec![0]), rng)
+            .unwrap();
+        game.process_action(&PlayerAction::FinishTurn, rng).unwrap();
+        let view = PlayerView::new(&game, 1, &[false, true, true]);
+        // Views survive a round trip through JSON, as sent to clients.
+        let json = serde_json::to_string(&view).unwrap();
+        let view: PlayerView = serde_json::from_str(&json).unwrap();
+
+        let sampler = ViewSampler::new(&view).unwrap();
+        let sample = sampler.sample(rng);
+        assert_eq!(sample.curr_player_idx, 1);
+        assert_eq!(sample.bonus_counts(), game.bonus_counts());
+        for (p, q) in sample.players.iter().zip(&game.players) {
+            assert_eq!(p.position, q.position);
+            assert_eq!(p.hand.len(), q.hand.len());
+            assert_eq!(p.all_cards(), q.all_cards());
+            assert_eq!(p.deck_size(), q.deck_size());
+        }
+        assert_eq!(sample.players[1].hand, game.players[1].hand);
+        // Other players' hands are hidden.
+        let json = serde_json::to_value(&view).unwrap();
+        assert!(json["my_player"]["hand"].is_array());
+        for p in json["other_players"].as_array().unwrap() {
+            assert!(p.get("hand").is_none());
+        }
+        assert_eq!(sample.players[1].can_buy, game.players[1].can_buy);
+    }
+
+    #[test]
+    fn used_tokens_stay_out_of_caves() {
+        let rng = &mut rand::rng();
+        let mut game =
+            GameState::new(3, "easy1", &RuleSet::default(), rng).unwrap();
+        // Player 0 collects every DrawCard token, then uses them all.
+        for (_, tokens) in game.bonuses_mut() {
+            tokens.retain(|t| *t != BonusToken::DrawCard);
+        }
+        let num_draws = data::ALL_BONUS_TOKENS
+            .iter()
+            .filter(|&&t| t == BonusToken::DrawCard)
+            .count();
+        game.players[0].tokens = vec![BonusToken::DrawCard; num_draws];
+        game.rehash();
+        let draw = PlayerAction::Draw(DrawAction {
+            card: None,
+            token: Some(0),
+        });
+        for _ in 0..num_draws {
+            game.process_action(&draw, rng).unwrap();
+        }
+        assert!(game.players[0].tokens.is_empty());
+        assert_eq!(game.players[0].used_tokens.len(), num_draws);
+
+        let sampler =
+            ViewSampler::new(&PlayerView::new(&game, 1, &[])).unwrap();
+        for _ in 0..20 {
+            let mut sample = sampler.sample(rng);
+            for (_, tokens) in sample.bonuses_mut() {
+                assert!(!tokens.contains(&BonusToken::DrawCard));
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::hash::{Hash, Hasher};
+
+// FNV-1a with a final mix, writing integers as little-endian. Unlike
+// DefaultHasher, its output is the same on every platform and Rust release,
+// so hashes are reproducible.
+struct StableHasher(u64);
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    fn write_u16(&mut self, n: u16) {
+        self.write(&n.to_le_bytes());
+    }
+    fn write_u32(&mut self, n: u32) {
+        self.write(&n.to_le_bytes());
+    }
+    fn write_u64(&mut self, n: u64) {
+        self.write(&n.to_le_bytes());
+    }
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+    fn write_isize(&mut self, n: isize) {
+        self.write_u64(n as u64);
+    }
+    fn finish(&self) -> u64 {
+        // The SplitMix64 finalizer, to spread FNV's weak low bits.
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+pub(crate) fn hash_of(value: &impl Hash) -> u64 {
+    let mut hasher = StableHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A Zobrist-style hash of a collection, kept up to date as items come and
+/// go: each item has a fixed key, and the hash is the sum of their keys.
+/// Keys are added and subtracted rather than XORed, so that equal items
+/// (like two copies of a card) don't cancel out.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub(crate) struct ZobristHash(u64);
+
+impl ZobristHash {
+    pub(crate) fn add(&mut self, item: &impl Hash) {
+        self.0 = self.0.wrapping_add(hash_of(item));
+    }
+    pub(crate) fn remove(&mut self, item: &impl Hash) {
+        self.0 = self.0.wrapping_sub(hash_of(item));
+    }
+    /// Move `item` from the `from` pile to the `to` pile, where piles keep
+    /// equal items in different places apart.
+    pub(crate) fn shift(&mut self, item: &impl Hash, from: u8, to: u8) {
+        self.remove(&(from, item));
+        self.add(&(to, item));
+    }
+    pub(crate) fn value(self) -> u64 {
+        self.0
+    }
+}
+
+impl<T: Hash> FromIterator<T> for ZobristHash {
+    fn from_iter<I: IntoIterator<Item = T>>(items: I) -> Self {
+        let mut hash = Self::default();
+        for item in items {
+            hash.add(&item);
+        }
+        hash
+    }
+}